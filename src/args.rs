@@ -0,0 +1,871 @@
+use crate::units::DurationUnit;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+/// An arg generated from a config, before it is rendered as tokens in a style.
+#[derive(Debug, PartialEq)]
+pub enum Arg {
+    /// An option named by its key path, like `["model", "layers"]` for
+    /// `--model.layers`, followed by its values.
+    Option {
+        key: Vec<String>,
+        values: Vec<String>,
+    },
+    /// A value printed without a flag, from a key starting with `_` or from a config
+    /// which is not an object.
+    Value(String),
+    /// Tokens already rendered from a key with `$` directives, which are kept together.
+    Tokens(Vec<String>),
+    /// A fragment printed verbatim, without being quoted for the shell.
+    Raw(String),
+}
+
+/// How options are rendered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Style {
+    /// `-k value` for single-character keys and `--key value` for the others.
+    #[default]
+    Gnu,
+    /// `-k=value` and `--key=value`.
+    Equals,
+    /// `-key value` whatever the length of the key is, like Go's flag package and
+    /// ffmpeg.
+    SingleDash,
+    /// `/key value`, for Windows tools.
+    Windows,
+    /// `/key:value`, for Windows tools.
+    WindowsColon,
+    /// `-Dkey=value` system properties for the JVM, with the values of an array joined
+    /// with commas.
+    Jvm,
+}
+
+impl FromStr for Style {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<Style> {
+        match name {
+            "gnu" => Ok(Style::Gnu),
+            "equals" => Ok(Style::Equals),
+            "single-dash" => Ok(Style::SingleDash),
+            "windows" => Ok(Style::Windows),
+            "windows-colon" => Ok(Style::WindowsColon),
+            "jvm" => Ok(Style::Jvm),
+            _ => anyhow::bail!("unsupported style: {name}"),
+        }
+    }
+}
+
+/// Whether the `Gnu` and `Equals` styles prefix a flag with one or two dashes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dashes {
+    /// `-k` for single-character keys and `--key` for the others.
+    #[default]
+    ByLength,
+    /// `--k` and `--key`.
+    Long,
+    /// `-k` and `-key`.
+    Short,
+}
+
+/// The case which the keys are rewritten in before they are printed as flags.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyCase {
+    /// Prints the keys as they are.
+    #[default]
+    Preserve,
+    /// `batch-size`.
+    Kebab,
+    /// `batch_size`.
+    Snake,
+    /// `batchSize`.
+    Camel,
+}
+
+impl FromStr for KeyCase {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<KeyCase> {
+        match name {
+            "preserve" => Ok(KeyCase::Preserve),
+            "kebab" => Ok(KeyCase::Kebab),
+            "snake" => Ok(KeyCase::Snake),
+            "camel" => Ok(KeyCase::Camel),
+            _ => anyhow::bail!("unsupported key case: {name}"),
+        }
+    }
+}
+
+impl KeyCase {
+    /// Rewrites a key in the case.
+    pub fn apply(self, key: &str) -> String {
+        if self == KeyCase::Preserve {
+            return key.to_string();
+        }
+
+        let words = words(key);
+        match self {
+            KeyCase::Preserve | KeyCase::Kebab => words.join("-"),
+            KeyCase::Snake => words.join("_"),
+            KeyCase::Camel => {
+                let mut key = String::new();
+                for (i, word) in words.iter().enumerate() {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) if i > 0 => key.extend(first.to_uppercase().chain(chars)),
+                        _ => key.push_str(word),
+                    }
+                }
+                key
+            }
+        }
+    }
+}
+
+/// Splits a key into lowercase words at `-`, `_` and the boundaries of camelCase, like
+/// `["http", "server", "port"]` for `HTTPServer_port`.
+fn words(key: &str) -> Vec<String> {
+    let chars: Vec<char> = key.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '-' || c == '_' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+
+        if c.is_uppercase() && !word.is_empty() {
+            let previous = chars[i - 1];
+            let next_is_lowercase = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if !previous.is_uppercase() || next_is_lowercase {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+/// How the values of an array are printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrayStyle {
+    /// `--key 1 2 3`.
+    #[default]
+    Space,
+    /// `--key 1 --key 2 --key 3`.
+    Repeat,
+}
+
+impl FromStr for ArrayStyle {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<ArrayStyle> {
+        match name {
+            "space" => Ok(ArrayStyle::Space),
+            "repeat" => Ok(ArrayStyle::Repeat),
+            _ => anyhow::bail!("unsupported array style: {name}"),
+        }
+    }
+}
+
+/// How the arrays of objects, and the arrays of arrays, are printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NestedArrays {
+    /// Repeats the group of flags of every object, like `--hosts.name a --hosts.port 1
+    /// --hosts.name b --hosts.port 2`.
+    #[default]
+    Group,
+    /// Puts the index of every item in the key path, like `--hosts.0.name a` and
+    /// `--key.1.0 4` for `{"key": [3, [4]]}`. Only this mode supports arrays of arrays.
+    Indexed,
+}
+
+impl FromStr for NestedArrays {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<NestedArrays> {
+        match name {
+            "group" => Ok(NestedArrays::Group),
+            "indexed" => Ok(NestedArrays::Indexed),
+            _ => anyhow::bail!("unsupported nested arrays: {name}"),
+        }
+    }
+}
+
+/// How numbers which are not integers are printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// The shortest digits which parse back to the same number, like `0.00001`.
+    #[default]
+    Shortest,
+    /// A fixed number of digits after the decimal point, like `0.10` for 2.
+    Fixed(usize),
+    /// The shortest digits in scientific notation, like `1e-5`.
+    Scientific,
+}
+
+impl FromStr for FloatFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<FloatFormat> {
+        match name.split_once(':') {
+            Some(("fixed", precision)) => {
+                Ok(FloatFormat::Fixed(precision.parse().map_err(|_| {
+                    anyhow::anyhow!("invalid precision of the float format: {precision}")
+                })?))
+            }
+            None if name == "shortest" => Ok(FloatFormat::Shortest),
+            None if name == "scientific" => Ok(FloatFormat::Scientific),
+            _ => anyhow::bail!("unsupported float format: {name}"),
+        }
+    }
+}
+
+impl FloatFormat {
+    /// Formats a number in the format.
+    pub fn format(self, number: f64) -> String {
+        match self {
+            FloatFormat::Shortest => number.to_string(),
+            FloatFormat::Fixed(precision) => format!("{number:.precision$}"),
+            FloatFormat::Scientific => format!("{number:e}"),
+        }
+    }
+}
+
+/// Where the positional args of the `_args` key are printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArgsPosition {
+    /// Before all the options.
+    Before,
+    /// After all the options.
+    #[default]
+    After,
+}
+
+impl FromStr for ArgsPosition {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<ArgsPosition> {
+        match name {
+            "before" => Ok(ArgsPosition::Before),
+            "after" => Ok(ArgsPosition::After),
+            _ => anyhow::bail!("unsupported args position: {name}"),
+        }
+    }
+}
+
+/// How null values in a config are printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NullPolicy {
+    /// Omits both the flag and the value.
+    #[default]
+    SkipPair,
+    /// Prints the flag without a value, like `--key`.
+    FlagOnly,
+    /// Prints `null` as the value, like `--key null`.
+    LiteralNull,
+    /// Fails to generate the args.
+    Error,
+}
+
+impl FromStr for NullPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<NullPolicy> {
+        match name {
+            "skip-pair" => Ok(NullPolicy::SkipPair),
+            "flag-only" => Ok(NullPolicy::FlagOnly),
+            "literal-null" => Ok(NullPolicy::LiteralNull),
+            "error" => Ok(NullPolicy::Error),
+            _ => anyhow::bail!("unsupported null policy: {name}"),
+        }
+    }
+}
+
+/// How options printed with the same flag more than once are handled, like the keys
+/// renamed to the same flag.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dedup {
+    /// Prints every option.
+    #[default]
+    Keep,
+    /// Prints only the last option with the flag.
+    Last,
+    /// Prints only the first option with the flag.
+    First,
+    /// Fails to generate the args.
+    Error,
+}
+
+impl FromStr for Dedup {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<Dedup> {
+        match name {
+            "keep" => Ok(Dedup::Keep),
+            "last" => Ok(Dedup::Last),
+            "first" => Ok(Dedup::First),
+            "error" => Ok(Dedup::Error),
+            _ => anyhow::bail!("unsupported dedup policy: {name}"),
+        }
+    }
+}
+
+/// Options for generating args from a config and rendering them as tokens.
+#[derive(Clone, Debug)]
+pub struct ArgOptions {
+    pub style: Style,
+    pub dashes: Dashes,
+    /// Joins the keys of nested objects, like `.` for `--model.layers`.
+    pub separator: String,
+    pub key_case: KeyCase,
+    /// Maps the dotted key paths of a config to the names of the options, like
+    /// `{"learning_rate": "lr"}`.
+    pub renames: HashMap<String, String>,
+    /// Prints booleans as values like `--verbose true` instead of presence flags.
+    pub explicit_booleans: bool,
+    /// Prints `false` as a negated flag like `--no-verbose` instead of omitting it.
+    pub negate_false: bool,
+    pub null_policy: NullPolicy,
+    pub args_position: ArgsPosition,
+    pub array_style: ArrayStyle,
+    /// Overrides the array style for the dotted key paths of a config.
+    pub array_styles: HashMap<String, ArrayStyle>,
+    /// Joins the values of an array into one value, like `,` for `--key 1,2,3`.
+    pub array_delimiter: Option<String>,
+    pub nested_arrays: NestedArrays,
+    /// Prefix of the keys whose values are printed without a flag.
+    pub bare_prefix: String,
+    pub float_format: FloatFormat,
+    /// Joins a value starting with `-` to its flag, like `--key=-5`.
+    pub negative_equals: bool,
+    /// Dotted key paths of the integers printed as their flags repeated, like `-vvv`.
+    pub counts: Vec<String>,
+    pub dedup: Dedup,
+    /// Converts the strings holding durations, like `1h30m`, into numbers in the unit.
+    pub parse_durations: bool,
+    pub duration_unit: DurationUnit,
+    /// Dotted key paths of the strings holding sizes, like `2GiB`, converted into bytes.
+    pub sizes: Vec<String>,
+}
+
+impl Default for ArgOptions {
+    fn default() -> ArgOptions {
+        ArgOptions {
+            style: Style::default(),
+            dashes: Dashes::default(),
+            separator: ".".to_string(),
+            key_case: KeyCase::default(),
+            renames: HashMap::new(),
+            explicit_booleans: false,
+            negate_false: false,
+            null_policy: NullPolicy::default(),
+            args_position: ArgsPosition::default(),
+            array_style: ArrayStyle::default(),
+            array_styles: HashMap::new(),
+            array_delimiter: None,
+            nested_arrays: NestedArrays::default(),
+            bare_prefix: "_".to_string(),
+            float_format: FloatFormat::default(),
+            negative_equals: false,
+            counts: Vec::new(),
+            dedup: Dedup::default(),
+            parse_durations: false,
+            duration_unit: DurationUnit::default(),
+            sizes: Vec::new(),
+        }
+    }
+}
+
+impl ArgOptions {
+    /// Returns whether the integer at the key path of a config is a count.
+    pub fn is_count(&self, key: &[String]) -> bool {
+        let path = key.join(".");
+        self.counts.contains(&path)
+    }
+
+    /// Returns whether the strings at the key path of a config are sizes.
+    pub fn is_size(&self, key: &[String]) -> bool {
+        let path = key.join(".");
+        self.sizes.contains(&path)
+    }
+
+    /// Returns whether the values of a top-level key are printed without a flag.
+    pub fn is_bare(&self, key: &str) -> bool {
+        !self.bare_prefix.is_empty() && key.starts_with(&self.bare_prefix)
+    }
+
+    /// Returns the array style for the key path of a config.
+    pub fn array_style(&self, key: &[String]) -> ArrayStyle {
+        self.array_styles
+            .get(&key.join("."))
+            .copied()
+            .unwrap_or(self.array_style)
+    }
+}
+
+/// Renames the longest leading part of a key path which is found in the renames, like
+/// `["net", "layers"]` for `["model", "layers"]` with `{"model": "net"}`.
+pub fn rename(key: Vec<String>, renames: &HashMap<String, String>) -> Vec<String> {
+    for len in (1..=key.len()).rev() {
+        if let Some(name) = renames.get(&key[..len].join(".")) {
+            let mut renamed: Vec<String> = name.split('.').map(str::to_string).collect();
+            renamed.extend_from_slice(&key[len..]);
+            return renamed;
+        }
+    }
+
+    key
+}
+
+/// Returns the name of an option, which is its key path rewritten in the key case and
+/// joined with the separator.
+pub fn name(key: &[String], options: &ArgOptions) -> String {
+    let keys: Vec<String> = key.iter().map(|key| options.key_case.apply(key)).collect();
+    keys.join(&options.separator)
+}
+
+/// Flattens the groups of args of every key, dropping the groups whose flags are printed
+/// by another group as well by the dedup policy. The options in a group, like the items
+/// of an array in the `Repeat` style, may share their flag.
+pub fn dedup(groups: Vec<Vec<Arg>>, options: &ArgOptions) -> anyhow::Result<Vec<Arg>> {
+    if options.dedup == Dedup::Keep {
+        return Ok(groups.into_iter().flatten().collect());
+    }
+
+    let names: Vec<HashSet<String>> = groups
+        .iter()
+        .map(|group| {
+            group
+                .iter()
+                .filter_map(|arg| match arg {
+                    Arg::Option { key, .. } => Some(name(key, options)),
+                    _ => None,
+                })
+                .collect()
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..groups.len()).collect();
+    if options.dedup == Dedup::Last {
+        order.reverse();
+    }
+
+    let mut seen = HashSet::new();
+    let mut kept = vec![true; groups.len()];
+    for index in order {
+        if let Some(name) = names[index].iter().find(|name| seen.contains(*name)) {
+            if options.dedup == Dedup::Error {
+                anyhow::bail!("duplicate flag: {name}");
+            }
+            kept[index] = false;
+        } else {
+            seen.extend(names[index].iter());
+        }
+    }
+
+    Ok(groups
+        .into_iter()
+        .zip(kept)
+        .filter(|(_, kept)| *kept)
+        .flat_map(|(group, _)| group)
+        .collect())
+}
+
+/// Renders the args as the tokens of a command line. In the `Equals` and
+/// `WindowsColon` styles, the first value of an option is joined to its flag and the
+/// other values follow it.
+pub fn tokens(args: &[Arg], options: &ArgOptions) -> Vec<String> {
+    token_groups(args, options).into_iter().flatten().collect()
+}
+
+/// Renders every arg as its own group of tokens, like `["--layers", "1", "2"]`.
+pub fn token_groups(args: &[Arg], options: &ArgOptions) -> Vec<Vec<String>> {
+    args.iter().map(|arg| arg_tokens(arg, options)).collect()
+}
+
+fn arg_tokens(arg: &Arg, options: &ArgOptions) -> Vec<String> {
+    match arg {
+        Arg::Option { key, values } => option_tokens(key, values, None, options),
+        Arg::Value(value) | Arg::Raw(value) => vec![value.clone()],
+        Arg::Tokens(tokens) => tokens.clone(),
+    }
+}
+
+/// The largest count printed as a repeated flag, which guards against a typo like
+/// `"v": 3000000000` generating gigabytes of args.
+const MAX_COUNT: u64 = 100;
+
+/// Renders a flag repeated `count` times, like `-vvv` for a flag of a single character
+/// and `--debug --debug` for the others.
+pub fn count_tokens(
+    key: &[String],
+    count: u64,
+    options: &ArgOptions,
+) -> anyhow::Result<Vec<String>> {
+    if count > MAX_COUNT {
+        anyhow::bail!("{}: a count must be at most {MAX_COUNT}", key.join("."));
+    }
+
+    let flag = option_tokens(key, &[], None, options).remove(0);
+    let count = count as usize;
+    let tokens = match flag.strip_prefix('-') {
+        Some(name) if count > 0 && name.chars().count() == 1 => {
+            vec![format!("-{}", name.repeat(count))]
+        }
+        _ => vec![flag; count],
+    };
+
+    Ok(tokens)
+}
+
+/// Renders an option as tokens in the style, with the flag given in place of the one
+/// named by its key.
+pub fn option_tokens(
+    key: &[String],
+    values: &[String],
+    flag: Option<&str>,
+    options: &ArgOptions,
+) -> Vec<String> {
+    let name = name(key, options);
+    let style = options.style;
+    let flag = match (flag, style) {
+        (Some(flag), _) => flag.to_string(),
+        (None, Style::Gnu | Style::Equals) => match options.dashes {
+            Dashes::ByLength if name.len() == 1 => format!("-{name}"),
+            Dashes::ByLength | Dashes::Long => format!("--{name}"),
+            Dashes::Short => format!("-{name}"),
+        },
+        (None, Style::SingleDash) => format!("-{name}"),
+        (None, Style::Windows | Style::WindowsColon) => format!("/{name}"),
+        (None, Style::Jvm) => format!("-D{name}"),
+    };
+    if style == Style::Jvm {
+        if values.is_empty() {
+            return vec![flag];
+        }
+        return vec![format!("{flag}={}", values.join(","))];
+    }
+
+    let is_negative = values.first().is_some_and(|value| value.starts_with('-'));
+    let joiner = match style {
+        Style::Equals => Some('='),
+        Style::WindowsColon => Some(':'),
+        // A value starting with `-` would be taken for a flag.
+        Style::Gnu | Style::SingleDash if options.negative_equals && is_negative => Some('='),
+        Style::Windows if options.negative_equals && is_negative => Some(':'),
+        Style::Gnu | Style::SingleDash | Style::Windows | Style::Jvm => None,
+    };
+
+    let mut tokens = Vec::new();
+    match (joiner, values.split_first()) {
+        (Some(joiner), Some((first, rest))) => {
+            tokens.push(format!("{flag}{joiner}{first}"));
+            tokens.extend(rest.iter().cloned());
+        }
+        _ => {
+            tokens.push(flag);
+            tokens.extend(values.iter().cloned());
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(style: Style) -> ArgOptions {
+        ArgOptions {
+            style,
+            ..ArgOptions::default()
+        }
+    }
+
+    fn args() -> Vec<Arg> {
+        vec![
+            Arg::Option {
+                key: vec!["model".to_string(), "layers".to_string()],
+                values: vec!["50".to_string()],
+            },
+            Arg::Option {
+                key: vec!["a".to_string()],
+                values: vec!["1".to_string(), "2".to_string()],
+            },
+            Arg::Option {
+                key: vec!["verbose".to_string()],
+                values: vec![],
+            },
+            Arg::Value("input.txt".to_string()),
+        ]
+    }
+
+    #[test]
+    fn tokens_in_gnu_style() {
+        assert_eq!(
+            tokens(&args(), &options(Style::Gnu)),
+            vec![
+                "--model.layers",
+                "50",
+                "-a",
+                "1",
+                "2",
+                "--verbose",
+                "input.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn token_groups_of_args() {
+        assert_eq!(
+            token_groups(&args(), &options(Style::Gnu)),
+            vec![
+                vec!["--model.layers", "50"],
+                vec!["-a", "1", "2"],
+                vec!["--verbose"],
+                vec!["input.txt"],
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_with_dashes() {
+        let options = ArgOptions {
+            dashes: Dashes::Long,
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            tokens(&args(), &options),
+            vec![
+                "--model.layers",
+                "50",
+                "--a",
+                "1",
+                "2",
+                "--verbose",
+                "input.txt"
+            ]
+        );
+        let options = ArgOptions {
+            style: Style::Equals,
+            dashes: Dashes::Short,
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            tokens(&args(), &options),
+            vec!["-model.layers=50", "-a=1", "2", "-verbose", "input.txt"]
+        );
+    }
+
+    #[test]
+    fn tokens_with_separator() {
+        let options = ArgOptions {
+            separator: "__".to_string(),
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            tokens(&args()[..1], &options),
+            vec!["--model__layers", "50"]
+        );
+    }
+
+    #[test]
+    fn rewrite_key_case() {
+        assert_eq!(KeyCase::Kebab.apply("batchSize"), "batch-size");
+        assert_eq!(KeyCase::Kebab.apply("HTTPServer_port"), "http-server-port");
+        assert_eq!(KeyCase::Snake.apply("batch-size"), "batch_size");
+        assert_eq!(KeyCase::Camel.apply("batch_size"), "batchSize");
+        assert_eq!(KeyCase::Camel.apply("layer2-norm"), "layer2Norm");
+        assert_eq!(KeyCase::Preserve.apply("batch_Size"), "batch_Size");
+    }
+
+    #[test]
+    fn rename_key_paths() {
+        let renames = HashMap::from([
+            ("learning_rate".to_string(), "lr".to_string()),
+            ("model".to_string(), "net".to_string()),
+            ("model.layers".to_string(), "depth".to_string()),
+        ]);
+        let key = |path: &str| path.split('.').map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(rename(key("learning_rate"), &renames), key("lr"));
+        assert_eq!(rename(key("model.layers"), &renames), key("depth"));
+        assert_eq!(rename(key("model.width"), &renames), key("net.width"));
+        assert_eq!(rename(key("epochs"), &renames), key("epochs"));
+    }
+
+    #[test]
+    fn tokens_of_rendered_args() {
+        let args = vec![
+            Arg::Tokens(vec!["-l=0.1".to_string()]),
+            Arg::Raw("--weird=x;y".to_string()),
+        ];
+        assert_eq!(
+            token_groups(&args, &ArgOptions::default()),
+            vec![vec!["-l=0.1"], vec!["--weird=x;y"]]
+        );
+        let key = vec!["lr".to_string()];
+        assert_eq!(
+            option_tokens(
+                &key,
+                &["0.1".to_string()],
+                Some("-l"),
+                &options(Style::Equals)
+            ),
+            vec!["-l=0.1"]
+        );
+    }
+
+    #[test]
+    fn format_floats() {
+        let format = |name: &str, number| name.parse::<FloatFormat>().unwrap().format(number);
+        assert_eq!(format("shortest", 1e-5), "0.00001");
+        assert_eq!(format("shortest", 0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(format("fixed:2", 0.1 + 0.2), "0.30");
+        assert_eq!(format("scientific", 1e-5), "1e-5");
+        assert!("fixed".parse::<FloatFormat>().is_err());
+        assert!("fixed:x".parse::<FloatFormat>().is_err());
+    }
+
+    #[test]
+    fn tokens_with_negative_values() {
+        let args = vec![
+            Arg::Option {
+                key: vec!["min".to_string()],
+                values: vec!["-5".to_string(), "-1".to_string()],
+            },
+            Arg::Option {
+                key: vec!["max".to_string()],
+                values: vec!["5".to_string()],
+            },
+        ];
+        let mut options = ArgOptions {
+            negative_equals: true,
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            tokens(&args, &options),
+            vec!["--min=-5", "-1", "--max", "5"]
+        );
+        options.style = Style::Windows;
+        assert_eq!(tokens(&args, &options), vec!["/min:-5", "-1", "/max", "5"]);
+        assert_eq!(
+            tokens(&args, &ArgOptions::default()),
+            vec!["--min", "-5", "-1", "--max", "5"]
+        );
+    }
+
+    #[test]
+    fn dedup_options() {
+        let option = |key: &str, value: &str| Arg::Option {
+            key: vec![key.to_string()],
+            values: vec![value.to_string()],
+        };
+        let args = || {
+            vec![
+                vec![option("lr", "0.1")],
+                vec![option("tag", "a"), option("tag", "b")],
+                vec![Arg::Value("data".to_string())],
+                vec![option("lr", "0.2")],
+            ]
+        };
+        let options = |dedup| ArgOptions {
+            dedup,
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            dedup(args(), &options(Dedup::Keep)).unwrap(),
+            args().into_iter().flatten().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            tokens(
+                &dedup(args(), &options(Dedup::Last)).unwrap(),
+                &options(Dedup::Last)
+            ),
+            vec!["--tag", "a", "--tag", "b", "data", "--lr", "0.2"]
+        );
+        assert_eq!(
+            tokens(
+                &dedup(args(), &options(Dedup::First)).unwrap(),
+                &options(Dedup::First)
+            ),
+            vec!["--lr", "0.1", "--tag", "a", "--tag", "b", "data"]
+        );
+        assert!(dedup(args(), &options(Dedup::Error)).is_err());
+    }
+
+    #[test]
+    fn tokens_of_counts() {
+        let key = |name: &str| vec![name.to_string()];
+        let options = ArgOptions::default();
+        assert_eq!(count_tokens(&key("v"), 3, &options).unwrap(), vec!["-vvv"]);
+        assert_eq!(
+            count_tokens(&key("debug"), 2, &options).unwrap(),
+            vec!["--debug", "--debug"]
+        );
+        assert!(count_tokens(&key("v"), 0, &options).unwrap().is_empty());
+        assert_eq!(
+            count_tokens(&key("v"), 2, &self::options(Style::Windows)).unwrap(),
+            vec!["/v", "/v"]
+        );
+        assert!(count_tokens(&key("v"), u64::MAX, &options).is_err());
+    }
+
+    #[test]
+    fn tokens_in_equals_style() {
+        assert_eq!(
+            tokens(&args(), &options(Style::Equals)),
+            vec!["--model.layers=50", "-a=1", "2", "--verbose", "input.txt"]
+        );
+    }
+
+    #[test]
+    fn tokens_in_single_dash_style() {
+        assert_eq!(
+            tokens(&args(), &options(Style::SingleDash)),
+            vec![
+                "-model.layers",
+                "50",
+                "-a",
+                "1",
+                "2",
+                "-verbose",
+                "input.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_in_windows_style() {
+        assert_eq!(
+            tokens(&args(), &options(Style::Windows)),
+            vec![
+                "/model.layers",
+                "50",
+                "/a",
+                "1",
+                "2",
+                "/verbose",
+                "input.txt"
+            ]
+        );
+        assert_eq!(
+            tokens(&args(), &options(Style::WindowsColon)),
+            vec!["/model.layers:50", "/a:1", "2", "/verbose", "input.txt"]
+        );
+    }
+
+    #[test]
+    fn tokens_in_jvm_style() {
+        assert_eq!(
+            tokens(&args(), &options(Style::Jvm)),
+            vec!["-Dmodel.layers=50", "-Da=1,2", "-Dverbose", "input.txt"]
+        );
+    }
+}
@@ -0,0 +1,557 @@
+use crate::args::{ArgOptions, Dashes};
+use crate::format::Format;
+use crate::merge::MergeOptions;
+use crate::order::Sort;
+use crate::output::{Output, OutputOptions};
+use crate::preset::Preset;
+use crate::source;
+use crate::template::Engine;
+use serde_json::Value;
+use std::slice::Iter;
+
+#[derive(Debug, Default)]
+pub struct Options {
+    pub config_paths: Vec<String>,
+    pub format: Option<Format>,
+    pub profile: Option<String>,
+    /// File whose values fill in the keys missing in the config, given with `--defaults`.
+    pub defaults: Option<String>,
+    pub merge: MergeOptions,
+    /// Values set with `--set KEY=VALUE`, in the order they are given.
+    pub overrides: Vec<(String, Value)>,
+    /// Prefix of the environment variables which override values, like `C2A`.
+    pub env_prefix: Option<String>,
+    pub resolve_vault: bool,
+    /// Identity file decrypting age-encrypted configs.
+    pub age_identity: Option<String>,
+    pub args: ArgOptions,
+    /// Path to the tera template rendered with the config, given with `--template`.
+    pub template: Option<String>,
+    /// Directory of the tera templates, given with `--template-dir`.
+    pub template_dir: Option<String>,
+    /// Evaluates every string value of the config as its own template.
+    pub template_values: bool,
+    /// Engine given with `--engine`, which is otherwise inferred from the extension
+    /// of the templates.
+    pub engine: Option<Engine>,
+    /// Variables of the templates set with `--var NAME=VALUE`, in the order they are given.
+    pub vars: Vec<(String, Value)>,
+    /// Path to the map renaming the keys, given with `--rename-map`.
+    pub rename_map: Option<String>,
+    pub sort: Sort,
+    pub output: OutputOptions,
+    pub preset: Option<Preset>,
+    /// Path to the launcher script written with `--emit-script`.
+    pub emit_script: Option<String>,
+    /// Path to the file written with `--output` instead of stdout.
+    pub output_path: Option<String>,
+    /// JSON Pointer to the subtree to generate the args from.
+    pub path: Option<String>,
+    /// jq filter transforming the config, given with `--query`.
+    pub query: Option<String>,
+    /// JMESPath expression selecting the config, given with `--jmespath`.
+    pub jmespath: Option<String>,
+    /// Glob patterns of the dotted key paths dropped from the config.
+    pub exclude: Vec<String>,
+    /// Glob patterns of the dotted key paths kept in the config, given with `--only`.
+    pub only: Vec<String>,
+    /// Dotted key paths which the config must have, given with `--require`.
+    pub require: Vec<String>,
+}
+
+pub fn parse_args(args: &[String]) -> anyhow::Result<Options> {
+    let mut options = Options::default();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => options.format = Some(next_value(&mut iter, arg)?.parse()?),
+            "--profile" => options.profile = Some(next_value(&mut iter, arg)?.clone()),
+            "--defaults" => options.defaults = Some(next_value(&mut iter, arg)?.clone()),
+            "--array-merge" => {
+                let value = next_value(&mut iter, arg)?;
+                match value.split_once('=') {
+                    Some((path, strategy)) => {
+                        options
+                            .merge
+                            .array_overrides
+                            .insert(path.to_string(), strategy.parse()?);
+                    }
+                    None => options.merge.arrays = value.parse()?,
+                }
+            }
+            "--set" => {
+                let value = next_value(&mut iter, arg)?;
+                let (path, value) = value
+                    .split_once('=')
+                    .ok_or_else(|| anyhow::anyhow!("--set requires KEY=VALUE: {value}"))?;
+                options
+                    .overrides
+                    .push((path.to_string(), parse_value(value)));
+            }
+            "--env-prefix" => options.env_prefix = Some(next_value(&mut iter, arg)?.clone()),
+            "--resolve-vault" => options.resolve_vault = true,
+            "--age-identity" => options.age_identity = Some(next_value(&mut iter, arg)?.clone()),
+            "--no-quote" => options.output.no_quote = true,
+            "--style" => options.args.style = next_value(&mut iter, arg)?.parse()?,
+            "--always-long" => options.args.dashes = Dashes::Long,
+            "--always-short" => options.args.dashes = Dashes::Short,
+            "--separator" => options.args.separator = next_value(&mut iter, arg)?.clone(),
+            "--key-case" => options.args.key_case = next_value(&mut iter, arg)?.parse()?,
+            "--template" => options.template = Some(next_value(&mut iter, arg)?.clone()),
+            "--var" => {
+                let value = next_value(&mut iter, arg)?;
+                let (name, value) = value
+                    .split_once('=')
+                    .ok_or_else(|| anyhow::anyhow!("--var requires NAME=VALUE: {value}"))?;
+                options.vars.push((name.to_string(), parse_value(value)));
+            }
+            "--engine" => options.engine = Some(next_value(&mut iter, arg)?.parse()?),
+            "--template-values" => options.template_values = true,
+            "--template-dir" => options.template_dir = Some(next_value(&mut iter, arg)?.clone()),
+            "--rename-map" => options.rename_map = Some(next_value(&mut iter, arg)?.clone()),
+            "--sort" => options.sort = next_value(&mut iter, arg)?.parse()?,
+            "--args-position" => {
+                options.args.args_position = next_value(&mut iter, arg)?.parse()?
+            }
+            "--array-style" => {
+                let value = next_value(&mut iter, arg)?;
+                match value.split_once('=') {
+                    Some((key, style)) => {
+                        options
+                            .args
+                            .array_styles
+                            .insert(key.to_string(), style.parse()?);
+                    }
+                    None => options.args.array_style = value.parse()?,
+                }
+            }
+            "--array-delimiter" => {
+                options.args.array_delimiter = Some(next_value(&mut iter, arg)?.clone())
+            }
+            "--nested-arrays" => {
+                options.args.nested_arrays = next_value(&mut iter, arg)?.parse()?
+            }
+            "--bare-prefix" => options.args.bare_prefix = next_value(&mut iter, arg)?.clone(),
+            "--float-format" => options.args.float_format = next_value(&mut iter, arg)?.parse()?,
+            "--negative-equals" => options.args.negative_equals = true,
+            "--count" => options
+                .args
+                .counts
+                .extend(next_value(&mut iter, arg)?.split(',').map(str::to_string)),
+            "--dedup" => options.args.dedup = next_value(&mut iter, arg)?.parse()?,
+            "--parse-durations" => options.args.parse_durations = true,
+            "--duration-unit" => {
+                options.args.duration_unit = next_value(&mut iter, arg)?.parse()?
+            }
+            "--parse-sizes" => options
+                .args
+                .sizes
+                .extend(next_value(&mut iter, arg)?.split(',').map(str::to_string)),
+            "--explicit-booleans" => options.args.explicit_booleans = true,
+            "--negate-false" => options.args.negate_false = true,
+            "--null-policy" => options.args.null_policy = next_value(&mut iter, arg)?.parse()?,
+            "--preset" => options.preset = Some(next_value(&mut iter, arg)?.parse()?),
+            "--program" => options.output.program = Some(next_value(&mut iter, arg)?.clone()),
+            "--emit-script" => options.emit_script = Some(next_value(&mut iter, arg)?.clone()),
+            "--pretty" => options.output.pretty = true,
+            "-o" | "--output" => options.output_path = Some(next_value(&mut iter, arg)?.clone()),
+            "--output-format" => options.output.format = next_value(&mut iter, arg)?.parse()?,
+            "--print0" => options.output.format = Output::Print0,
+            "--lines" => options.output.format = Output::Lines,
+            "--path" => options.path = Some(next_value(&mut iter, arg)?.clone()),
+            "--exclude" => options
+                .exclude
+                .extend(next_value(&mut iter, arg)?.split(',').map(str::to_string)),
+            "--only" => options
+                .only
+                .extend(next_value(&mut iter, arg)?.split(',').map(str::to_string)),
+            "--require" => options
+                .require
+                .extend(next_value(&mut iter, arg)?.split(',').map(str::to_string)),
+            "--query" => options.query = Some(next_value(&mut iter, arg)?.clone()),
+            "--jmespath" => options.jmespath = Some(next_value(&mut iter, arg)?.clone()),
+            "--stdin" => add_config_path(&mut options.config_paths, source::STDIN)?,
+            flag if flag.starts_with("--") => anyhow::bail!("unknown option: {flag}"),
+            _ => add_config_path(&mut options.config_paths, arg)?,
+        }
+    }
+
+    if options.config_paths.is_empty() {
+        anyhow::bail!("config file path is required");
+    }
+    if options.emit_script.is_some() && options.output_path.is_some() {
+        anyhow::bail!("--emit-script cannot be used with --output");
+    }
+
+    Ok(options)
+}
+
+fn add_config_path(config_paths: &mut Vec<String>, path: &str) -> anyhow::Result<()> {
+    if path == source::STDIN && config_paths.iter().any(|path| path == source::STDIN) {
+        anyhow::bail!("stdin can only be read once");
+    }
+    config_paths.push(path.to_string());
+
+    Ok(())
+}
+
+/// Parses a value given on the command line as JSON, falling back to a string so that
+/// `--set out=logs` does not need quotes.
+pub fn parse_value(value: &str) -> Value {
+    serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()))
+}
+
+fn next_value<'a>(iter: &mut Iter<'a, String>, flag: &str) -> anyhow::Result<&'a String> {
+    iter.next()
+        .ok_or_else(|| anyhow::anyhow!("{flag} requires a value"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::{
+        ArgsPosition, ArrayStyle, Dedup, FloatFormat, KeyCase, NestedArrays, NullPolicy, Style,
+    };
+    use crate::merge::ArrayStrategy;
+    use crate::units::DurationUnit;
+    use serde_json::json;
+
+    fn args(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_args_with_format() {
+        let options = parse_args(&args(&["--format", "yaml", "config.txt"])).unwrap();
+        assert_eq!(options.config_paths, vec!["config.txt"]);
+        assert_eq!(options.format, Some(Format::Yaml));
+    }
+
+    #[test]
+    fn parse_args_with_stdin() {
+        let options = parse_args(&args(&["--stdin", "--format", "toml"])).unwrap();
+        assert_eq!(options.config_paths, vec!["-"]);
+        assert_eq!(parse_args(&args(&["-"])).unwrap().config_paths, vec!["-"]);
+        assert!(parse_args(&args(&["--stdin", "-"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_with_multiple_config_paths() {
+        let options = parse_args(&args(&["base.json", "-", "override.yml"])).unwrap();
+        assert_eq!(options.config_paths, vec!["base.json", "-", "override.yml"]);
+    }
+
+    #[test]
+    fn parse_args_with_profile() {
+        let options = parse_args(&args(&["--profile", "dev", "config.json"])).unwrap();
+        assert_eq!(options.profile.as_deref(), Some("dev"));
+    }
+
+    #[test]
+    fn parse_args_with_template() {
+        let options = parse_args(&args(&["config.json", "--template", "launch.tera"])).unwrap();
+        assert_eq!(options.template.as_deref(), Some("launch.tera"));
+        assert_eq!(options.config_paths, vec!["config.json"]);
+    }
+
+    #[test]
+    fn parse_args_with_template_dir() {
+        let options = parse_args(&args(&[
+            "--template-dir",
+            "templates",
+            "--template",
+            "train.tera",
+            "config.json",
+        ]))
+        .unwrap();
+        assert_eq!(options.template_dir.as_deref(), Some("templates"));
+    }
+
+    #[test]
+    fn parse_args_with_template_values() {
+        let options = parse_args(&args(&["--template-values", "config.json.tera"])).unwrap();
+        assert!(options.template_values);
+    }
+
+    #[test]
+    fn parse_args_with_engine() {
+        let options = parse_args(&args(&["--engine", "handlebars", "config.json.hbs"])).unwrap();
+        assert_eq!(options.engine, Some(Engine::Handlebars));
+        let options = parse_args(&args(&["--engine", "minijinja", "config.yaml.j2"])).unwrap();
+        assert_eq!(options.engine, Some(Engine::MiniJinja));
+        assert!(parse_args(&args(&["--engine", "mustache", "config.json"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_with_var() {
+        let options = parse_args(&args(&[
+            "--var",
+            "run_id=a1",
+            "--var",
+            "gpu=3",
+            "config.json.tera",
+        ]))
+        .unwrap();
+        assert_eq!(
+            options.vars,
+            vec![
+                ("run_id".to_string(), json!("a1")),
+                ("gpu".to_string(), json!(3))
+            ]
+        );
+        assert!(parse_args(&args(&["--var", "gpu", "config.json.tera"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_with_defaults() {
+        let options = parse_args(&args(&["--defaults", "defaults.json", "config.json"])).unwrap();
+        assert_eq!(options.defaults.as_deref(), Some("defaults.json"));
+        assert_eq!(options.config_paths, vec!["config.json"]);
+    }
+
+    #[test]
+    fn parse_args_with_array_merge() {
+        let options = parse_args(&args(&[
+            "--array-merge",
+            "concat",
+            "--array-merge",
+            "train.include=union",
+            "config.json",
+        ]))
+        .unwrap();
+        assert_eq!(options.merge.arrays, ArrayStrategy::Concat);
+        assert_eq!(
+            options.merge.array_overrides.get("train.include"),
+            Some(&ArrayStrategy::Union)
+        );
+    }
+
+    #[test]
+    fn parse_args_with_set() {
+        let options = parse_args(&args(&[
+            "--set",
+            "training.lr=0.01",
+            "--set",
+            "out=logs/a",
+            "--set",
+            "layers=[1,2]",
+            "config.json",
+        ]))
+        .unwrap();
+        assert_eq!(
+            options.overrides,
+            vec![
+                ("training.lr".to_string(), json!(0.01)),
+                ("out".to_string(), json!("logs/a")),
+                ("layers".to_string(), json!([1, 2])),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_args_with_path() {
+        let options = parse_args(&args(&["--path", "/training", "config.json"])).unwrap();
+        assert_eq!(options.path.as_deref(), Some("/training"));
+    }
+
+    #[test]
+    fn parse_args_with_exclude() {
+        let options = parse_args(&args(&[
+            "--exclude",
+            "debug.*,seed",
+            "--exclude",
+            "serving",
+            "config.json",
+        ]))
+        .unwrap();
+        assert_eq!(options.exclude, vec!["debug.*", "seed", "serving"]);
+    }
+
+    #[test]
+    fn parse_args_with_only() {
+        let options = parse_args(&args(&["--only", "training.*,seed", "config.json"])).unwrap();
+        assert_eq!(options.only, vec!["training.*", "seed"]);
+    }
+
+    #[test]
+    fn parse_args_with_require() {
+        let options = parse_args(&args(&["--require", "model,data.path", "config.json"])).unwrap();
+        assert_eq!(options.require, vec!["model", "data.path"]);
+    }
+
+    #[test]
+    fn parse_args_with_output_format() {
+        let options = parse_args(&args(&["--output-format", "json", "config.json"])).unwrap();
+        assert_eq!(options.output.format, Output::Json);
+        let options = parse_args(&args(&["--print0", "config.json"])).unwrap();
+        assert_eq!(options.output.format, Output::Print0);
+        assert!(parse_args(&args(&["--output-format", "csv", "config.json"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_with_style() {
+        let options = parse_args(&args(&["--style", "jvm", "config.json"])).unwrap();
+        assert_eq!(options.args.style, Style::Jvm);
+        assert!(!options.args.explicit_booleans);
+    }
+
+    #[test]
+    fn parse_args_with_always_long() {
+        let options = parse_args(&args(&["--always-long", "config.json"])).unwrap();
+        assert_eq!(options.args.dashes, Dashes::Long);
+    }
+
+    #[test]
+    fn parse_args_with_separator() {
+        let options = parse_args(&args(&["--separator", "-", "config.json"])).unwrap();
+        assert_eq!(options.args.separator, "-");
+    }
+
+    #[test]
+    fn parse_args_with_key_case() {
+        let options = parse_args(&args(&["--key-case", "kebab", "config.json"])).unwrap();
+        assert_eq!(options.args.key_case, KeyCase::Kebab);
+    }
+
+    #[test]
+    fn parse_args_with_sort() {
+        let options = parse_args(&args(&["--sort", "alpha", "config.json"])).unwrap();
+        assert_eq!(options.sort, Sort::Alpha);
+    }
+
+    #[test]
+    fn parse_args_with_args_position() {
+        let options = parse_args(&args(&["--args-position", "before", "config.json"])).unwrap();
+        assert_eq!(options.args.args_position, ArgsPosition::Before);
+    }
+
+    #[test]
+    fn parse_args_with_array_style() {
+        let options = parse_args(&args(&[
+            "--array-style",
+            "repeat",
+            "--array-style",
+            "tags=space",
+            "config.json",
+        ]))
+        .unwrap();
+        assert_eq!(options.args.array_style, ArrayStyle::Repeat);
+        assert_eq!(options.args.array_styles["tags"], ArrayStyle::Space);
+    }
+
+    #[test]
+    fn parse_args_with_array_delimiter() {
+        let options = parse_args(&args(&["--array-delimiter", ",", "config.json"])).unwrap();
+        assert_eq!(options.args.array_delimiter.as_deref(), Some(","));
+    }
+
+    #[test]
+    fn parse_args_with_nested_arrays() {
+        let options = parse_args(&args(&["--nested-arrays", "indexed", "config.json"])).unwrap();
+        assert_eq!(options.args.nested_arrays, NestedArrays::Indexed);
+    }
+
+    #[test]
+    fn parse_args_with_bare_prefix() {
+        let options = parse_args(&args(&["--bare-prefix", "@", "config.json"])).unwrap();
+        assert_eq!(options.args.bare_prefix, "@");
+    }
+
+    #[test]
+    fn parse_args_with_float_format() {
+        let options = parse_args(&args(&["--float-format", "fixed:3", "config.json"])).unwrap();
+        assert_eq!(options.args.float_format, FloatFormat::Fixed(3));
+    }
+
+    #[test]
+    fn parse_args_with_negative_equals() {
+        let options = parse_args(&args(&["--negative-equals", "config.json"])).unwrap();
+        assert!(options.args.negative_equals);
+    }
+
+    #[test]
+    fn parse_args_with_count() {
+        let options = parse_args(&args(&["--count", "v,debug", "config.json"])).unwrap();
+        assert_eq!(options.args.counts, vec!["v", "debug"]);
+    }
+
+    #[test]
+    fn parse_args_with_dedup() {
+        let options = parse_args(&args(&["--dedup", "last", "config.json"])).unwrap();
+        assert_eq!(options.args.dedup, Dedup::Last);
+        assert!(parse_args(&args(&["--dedup", "never", "config.json"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_with_parse_durations() {
+        let options = parse_args(&args(&[
+            "--parse-durations",
+            "--duration-unit",
+            "ms",
+            "config.json",
+        ]))
+        .unwrap();
+        assert!(options.args.parse_durations);
+        assert_eq!(options.args.duration_unit, DurationUnit::Milliseconds);
+    }
+
+    #[test]
+    fn parse_args_with_parse_sizes() {
+        let options = parse_args(&args(&["--parse-sizes", "memory,cache", "config.json"])).unwrap();
+        assert_eq!(options.args.sizes, vec!["memory", "cache"]);
+    }
+
+    #[test]
+    fn parse_args_with_explicit_booleans() {
+        let options = parse_args(&args(&["--explicit-booleans", "config.json"])).unwrap();
+        assert!(options.args.explicit_booleans);
+    }
+
+    #[test]
+    fn parse_args_with_negate_false() {
+        let options = parse_args(&args(&["--negate-false", "config.json"])).unwrap();
+        assert!(options.args.negate_false);
+    }
+
+    #[test]
+    fn parse_args_with_null_policy() {
+        let options = parse_args(&args(&["--null-policy", "flag-only", "config.json"])).unwrap();
+        assert_eq!(options.args.null_policy, NullPolicy::FlagOnly);
+        assert!(parse_args(&args(&["--null-policy", "drop", "config.json"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_with_output() {
+        let options = parse_args(&args(&["-o", "args.txt", "config.json"])).unwrap();
+        assert_eq!(options.output_path.as_deref(), Some("args.txt"));
+        let options = parse_args(&args(&["config.json", "--output", "args.txt"])).unwrap();
+        assert_eq!(options.output_path.as_deref(), Some("args.txt"));
+    }
+
+    #[test]
+    fn parse_args_with_emit_script() {
+        let options = parse_args(&args(&["--emit-script", "train.sh", "config.json"])).unwrap();
+        assert_eq!(options.emit_script.as_deref(), Some("train.sh"));
+        assert!(
+            parse_args(&args(&[
+                "--emit-script",
+                "train.sh",
+                "-o",
+                "args.txt",
+                "config.json"
+            ]))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_args_without_config_path() {
+        assert!(parse_args(&args(&["--format", "yaml"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_with_unknown_option() {
+        assert!(parse_args(&args(&["--unknown", "config.json"])).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use crate::format::Format;
+use crate::source;
+use serde_json::Value;
+use std::process::Command;
+
+/// Environment variable holding the path to the identity file for age-encrypted configs.
+pub const AGE_IDENTITY_ENV: &str = "CONFIG2ARGS_AGE_IDENTITY";
+
+/// Returns whether the contents were encrypted by age, in the binary or the ASCII
+/// armored format.
+pub fn is_age_encrypted(contents: &[u8]) -> bool {
+    contents.starts_with(b"age-encryption.org/v1")
+        || contents.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----")
+}
+
+/// Decrypts age-encrypted contents with the `age` command and the identity file.
+pub fn age(path: &str, contents: &[u8], identity: Option<&str>) -> anyhow::Result<Vec<u8>> {
+    let identity = identity.ok_or_else(|| {
+        anyhow::anyhow!(
+            "{path}: the config is encrypted by age; give the identity file with \
+             --age-identity or {AGE_IDENTITY_ENV}"
+        )
+    })?;
+
+    let mut command = Command::new("age");
+    command.args(["--decrypt", "--identity", identity]);
+
+    source::run_with_input(path, &mut command, contents)
+}
+
+/// Returns whether the config was encrypted by SOPS, which adds a `sops` object
+/// holding the `mac` to JSON, YAML and INI files and `sops_mac` to dotenv files.
+pub fn is_sops_encrypted(documents: &[Value]) -> bool {
+    documents
+        .first()
+        .is_some_and(|config| !config["sops"]["mac"].is_null() || !config["sops_mac"].is_null())
+}
+
+/// Decrypts a SOPS-encrypted config with the `sops` command, so that the keys are
+/// found with the user's own SOPS setup (KMS, PGP, age, ...).
+pub fn sops(path: &str, contents: &[u8], format: Format) -> anyhow::Result<Vec<u8>> {
+    let file_type = match format {
+        Format::Json => "json",
+        Format::Yaml => "yaml",
+        Format::Ini => "ini",
+        Format::Dotenv => "dotenv",
+        _ => anyhow::bail!("{path}: sops cannot decrypt {format:?} configs"),
+    };
+
+    let mut command = Command::new("sops");
+    command.args([
+        "--decrypt",
+        "--input-type",
+        file_type,
+        "--output-type",
+        file_type,
+        "/dev/stdin",
+    ]);
+
+    source::run_with_input(path, &mut command, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn detect_age_encrypted_contents() {
+        assert!(is_age_encrypted(b"age-encryption.org/v1\n-> X25519 ..."));
+        assert!(is_age_encrypted(
+            b"-----BEGIN AGE ENCRYPTED FILE-----\nYWdlLWVuY3J5cHRpb24ub3JnL3YxCi0+IFgyNTUxOSA...\n"
+        ));
+        assert!(!is_age_encrypted(br#"{"lr": 0.1}"#));
+        assert!(age("config.json.age", b"age-encryption.org/v1", None).is_err());
+    }
+
+    #[test]
+    fn detect_sops_encrypted_configs() {
+        assert!(is_sops_encrypted(&[json!({
+            "lr": "ENC[AES256_GCM,data:...]",
+            "sops": {"mac": "ENC[AES256_GCM,data:...]", "version": "3.9.0"}
+        })]));
+        assert!(is_sops_encrypted(&[json!({"sops_mac": "ENC[...]"})]));
+        assert!(!is_sops_encrypted(&[json!({"sops": "not encrypted"})]));
+        assert!(!is_sops_encrypted(&[json!([1, 2])]));
+    }
+}
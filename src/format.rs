@@ -0,0 +1,632 @@
+mod dotenv;
+mod ini;
+mod jsonc;
+mod nestedtext;
+mod properties;
+#[cfg(feature = "xml")]
+mod xml;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+#[cfg(feature = "kdl")]
+use std::collections::HashSet;
+#[cfg(feature = "plist")]
+use std::io::Cursor;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+    Ini,
+    Json5,
+    Jsonc,
+    Xml,
+    Hcl,
+    Dotenv,
+    Properties,
+    Ron,
+    MessagePack,
+    Cbor,
+    NestedText,
+    Plist,
+    Kdl,
+    Csv,
+    Tsv,
+    JsonLines,
+}
+
+impl Format {
+    /// Guesses the format from the file extension. Trailing `.tera`, `.hbs`, `.j2`,
+    /// `.age`, `.gz` and `.zst` are ignored, so `config.yaml.tera` and `config.yaml.gz`
+    /// are detected as YAML.
+    pub fn from_path(path: &str) -> Option<Format> {
+        let mut path = path;
+        while let Some(stripped) = [".tera", ".hbs", ".j2", ".age", ".gz", ".zst"]
+            .iter()
+            .find_map(|suffix| path.strip_suffix(suffix))
+        {
+            path = stripped;
+        }
+        let file_name = Path::new(path).file_name()?.to_str()?;
+        if file_name == ".env" || file_name.starts_with(".env.") {
+            return Some(Format::Dotenv);
+        }
+
+        let extension = Path::new(path).extension()?.to_str()?;
+
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            "ini" => Some(Format::Ini),
+            "json5" => Some(Format::Json5),
+            "jsonc" => Some(Format::Jsonc),
+            "xml" => Some(Format::Xml),
+            "hcl" | "tf" => Some(Format::Hcl),
+            "env" => Some(Format::Dotenv),
+            "properties" => Some(Format::Properties),
+            "ron" => Some(Format::Ron),
+            "msgpack" | "mpk" => Some(Format::MessagePack),
+            "cbor" => Some(Format::Cbor),
+            "nt" => Some(Format::NestedText),
+            "plist" => Some(Format::Plist),
+            "kdl" => Some(Format::Kdl),
+            "csv" => Some(Format::Csv),
+            "tsv" => Some(Format::Tsv),
+            "jsonl" | "ndjson" => Some(Format::JsonLines),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the contents, for stdin and files whose extension does
+    /// not tell the format. Falls back to JSON so that the JSON parse error is shown.
+    pub fn detect(contents: &[u8]) -> Format {
+        if contents.starts_with(b"bplist") {
+            return Format::Plist;
+        }
+
+        let Ok(text) = std::str::from_utf8(contents) else {
+            #[cfg(feature = "msgpack")]
+            if rmp_serde::from_slice::<Value>(contents).is_ok() {
+                return Format::MessagePack;
+            }
+            #[cfg(feature = "cbor")]
+            if ciborium::de::from_reader::<Value, _>(contents).is_ok() {
+                return Format::Cbor;
+            }
+            return Format::Json;
+        };
+
+        let trimmed = text.trim_start();
+        if trimmed.starts_with('<') {
+            if trimmed.contains("<plist") {
+                return Format::Plist;
+            }
+            return Format::Xml;
+        }
+
+        if serde_json::from_str::<Value>(text).is_ok() {
+            return Format::Json;
+        }
+        if serde_json::from_str::<Value>(&jsonc::strip_comments(text)).is_ok() {
+            return Format::Jsonc;
+        }
+        if text.parse::<toml::Table>().is_ok() {
+            return Format::Toml;
+        }
+        if json5::from_str::<Value>(text).is_ok() {
+            return Format::Json5;
+        }
+        if let Ok(Value::Object(_) | Value::Array(_)) = serde_yaml::from_str::<Value>(text) {
+            return Format::Yaml;
+        }
+
+        Format::Json
+    }
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "toml" => Ok(Format::Toml),
+            "ini" => Ok(Format::Ini),
+            "json5" => Ok(Format::Json5),
+            "jsonc" => Ok(Format::Jsonc),
+            "xml" => Ok(Format::Xml),
+            "hcl" => Ok(Format::Hcl),
+            "dotenv" | "env" => Ok(Format::Dotenv),
+            "properties" => Ok(Format::Properties),
+            "ron" => Ok(Format::Ron),
+            "msgpack" | "messagepack" => Ok(Format::MessagePack),
+            "cbor" => Ok(Format::Cbor),
+            "nestedtext" | "nt" => Ok(Format::NestedText),
+            "plist" => Ok(Format::Plist),
+            "kdl" => Ok(Format::Kdl),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "jsonl" | "ndjson" | "jsonlines" => Ok(Format::JsonLines),
+            _ => anyhow::bail!("unsupported format: {name}"),
+        }
+    }
+}
+
+/// Parses the contents into the configs to convert. Batch formats such as CSV yield
+/// one config per record, a YAML file yields one config per `---` document (or an
+/// empty config when it has none), and every other format yields exactly one config.
+pub fn parse_documents(contents: &[u8], format: Format) -> anyhow::Result<Vec<Value>> {
+    if format == Format::Yaml {
+        let mut documents: Vec<Value> =
+            serde_yaml::Deserializer::from_str(std::str::from_utf8(contents)?)
+                .map(Value::deserialize)
+                .collect::<Result<_, _>>()?;
+        if documents.is_empty() {
+            documents.push(Value::Object(Map::new()));
+        }
+        return Ok(documents);
+    }
+
+    let documents = match (format, parse(contents, format)?) {
+        (Format::Csv | Format::Tsv | Format::JsonLines, Value::Array(records)) => records,
+        (_, config) => vec![config],
+    };
+
+    Ok(documents)
+}
+
+/// Parses the contents into a single config. The records of batch formats are
+/// returned as an array.
+pub fn parse(contents: &[u8], format: Format) -> anyhow::Result<Value> {
+    let config = match format {
+        #[cfg(feature = "msgpack")]
+        Format::MessagePack => rmp_serde::from_slice(contents)?,
+        #[cfg(not(feature = "msgpack"))]
+        Format::MessagePack => {
+            anyhow::bail!("MessagePack support is disabled; rebuild with `--features msgpack`")
+        }
+        #[cfg(feature = "cbor")]
+        Format::Cbor => ciborium::de::from_reader(contents)?,
+        #[cfg(not(feature = "cbor"))]
+        Format::Cbor => anyhow::bail!("CBOR support is disabled; rebuild with `--features cbor`"),
+        Format::Csv => parse_csv(contents, b',')?,
+        Format::Tsv => parse_csv(contents, b'\t')?,
+        #[cfg(feature = "plist")]
+        Format::Plist => convert_plist_value(plist::Value::from_reader(Cursor::new(contents))?)?,
+        #[cfg(not(feature = "plist"))]
+        Format::Plist => {
+            anyhow::bail!("plist support is disabled; rebuild with `--features plist`")
+        }
+        _ => parse_text(std::str::from_utf8(contents)?, format)?,
+    };
+
+    Ok(config)
+}
+
+fn parse_text(contents: &str, format: Format) -> anyhow::Result<Value> {
+    let config = match format {
+        Format::Json => serde_json::from_str(contents)?,
+        Format::Yaml => serde_yaml::from_str(contents)?,
+        Format::Toml => convert_toml_value(toml::Value::Table(contents.parse()?)),
+        Format::Ini => ini::parse(contents)?,
+        Format::Json5 => json5::from_str(contents)?,
+        Format::Jsonc => serde_json::from_str(&jsonc::strip_comments(contents))?,
+        #[cfg(feature = "xml")]
+        Format::Xml => xml::parse(contents)?,
+        #[cfg(not(feature = "xml"))]
+        Format::Xml => anyhow::bail!("XML support is disabled; rebuild with `--features xml`"),
+        #[cfg(feature = "hcl")]
+        Format::Hcl => hcl::from_str(contents)?,
+        #[cfg(not(feature = "hcl"))]
+        Format::Hcl => anyhow::bail!("HCL support is disabled; rebuild with `--features hcl`"),
+        Format::Dotenv => dotenv::parse(contents)?,
+        Format::Properties => properties::parse(contents)?,
+        Format::NestedText => coerce_numbers(nestedtext::parse(contents)?),
+        Format::JsonLines => parse_json_lines(contents)?,
+        #[cfg(feature = "kdl")]
+        Format::Kdl => Value::Object(convert_kdl_document(&contents.parse()?)?),
+        #[cfg(not(feature = "kdl"))]
+        Format::Kdl => anyhow::bail!("KDL support is disabled; rebuild with `--features kdl`"),
+        #[cfg(feature = "ron")]
+        Format::Ron => ron::from_str(contents)?,
+        #[cfg(not(feature = "ron"))]
+        Format::Ron => anyhow::bail!("RON support is disabled; rebuild with `--features ron`"),
+        Format::MessagePack | Format::Cbor | Format::Plist | Format::Csv | Format::Tsv => {
+            unreachable!("{format:?} is parsed from raw bytes")
+        }
+    };
+
+    Ok(config)
+}
+
+/// Parses CSV records with a header row. Each record becomes an object keyed by the
+/// header names, and empty cells are left out.
+fn parse_csv(contents: &[u8], delimiter: u8) -> anyhow::Result<Value> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(contents);
+    let headers = reader.headers()?.clone();
+
+    let mut records = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let object = headers
+            .iter()
+            .zip(record.iter())
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| (key.to_string(), Value::String(value.to_string())))
+            .collect();
+        records.push(Value::Object(object));
+    }
+
+    Ok(Value::Array(records))
+}
+
+fn parse_json_lines(contents: &str) -> anyhow::Result<Value> {
+    let mut records = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(line)
+            .map_err(|error| anyhow::anyhow!("line {}: {error}", index + 1))?;
+        records.push(record);
+    }
+
+    Ok(Value::Array(records))
+}
+
+/// Converts string values which look like numbers into numbers, for formats which
+/// have no types other than strings. A string is only converted when the number is
+/// rendered back exactly as written, so `"007"` and `"1.0"` are kept as they are.
+fn coerce_numbers(value: Value) -> Value {
+    match value {
+        Value::String(text) => {
+            if let Ok(number) = text.parse::<i64>() {
+                if number.to_string() == text {
+                    return Value::from(number);
+                }
+            }
+            if let Ok(number) = text.parse::<f64>() {
+                if number.is_finite() && number.to_string() == text {
+                    return Value::from(number);
+                }
+            }
+            Value::String(text)
+        }
+        Value::Array(values) => values.into_iter().map(coerce_numbers).collect(),
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (key, coerce_numbers(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+fn convert_toml_value(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(value) => Value::String(value),
+        toml::Value::Integer(value) => Value::from(value),
+        toml::Value::Float(value) => Value::from(value),
+        toml::Value::Boolean(value) => Value::Bool(value),
+        toml::Value::Datetime(value) => Value::String(value.to_string()),
+        toml::Value::Array(values) => values.into_iter().map(convert_toml_value).collect(),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, convert_toml_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(feature = "plist")]
+fn convert_plist_value(value: plist::Value) -> anyhow::Result<Value> {
+    let config = match value {
+        plist::Value::String(value) => Value::String(value),
+        plist::Value::Integer(value) => match value.as_signed() {
+            Some(value) => Value::from(value),
+            None => Value::from(value.as_unsigned().unwrap_or_default()),
+        },
+        plist::Value::Real(value) => Value::from(value),
+        plist::Value::Boolean(value) => Value::Bool(value),
+        plist::Value::Date(value) => Value::String(value.to_xml_format()),
+        plist::Value::Array(values) => values
+            .into_iter()
+            .map(convert_plist_value)
+            .collect::<anyhow::Result<Value>>()?,
+        plist::Value::Dictionary(dictionary) => Value::Object(
+            dictionary
+                .into_iter()
+                .map(|(key, value)| Ok((key, convert_plist_value(value)?)))
+                .collect::<anyhow::Result<Map<String, Value>>>()?,
+        ),
+        value => anyhow::bail!("unsupported plist value: {value:?}"),
+    };
+
+    Ok(config)
+}
+
+/// Converts KDL nodes into keys. The arguments of a node become its value (an array
+/// when there are several), and its properties and children become nested keys.
+/// Nodes which appear more than once are collected into an array.
+#[cfg(feature = "kdl")]
+fn convert_kdl_document(document: &kdl::KdlDocument) -> anyhow::Result<Map<String, Value>> {
+    let mut object = Map::new();
+    let mut repeated = HashSet::new();
+
+    for node in document.nodes() {
+        let name = node.name().value().to_string();
+        let value = convert_kdl_node(node)?;
+        match object.get_mut(&name) {
+            Some(Value::Array(values)) if repeated.contains(&name) => values.push(value),
+            Some(existing) => {
+                let first = existing.take();
+                *existing = Value::Array(vec![first, value]);
+                repeated.insert(name);
+            }
+            None => {
+                object.insert(name, value);
+            }
+        }
+    }
+
+    Ok(object)
+}
+
+#[cfg(feature = "kdl")]
+fn convert_kdl_node(node: &kdl::KdlNode) -> anyhow::Result<Value> {
+    let mut arguments = Vec::new();
+    let mut properties = Map::new();
+    for entry in node.entries() {
+        let value = convert_kdl_value(entry.value());
+        match entry.name() {
+            Some(name) => {
+                properties.insert(name.value().to_string(), value);
+            }
+            None => arguments.push(value),
+        }
+    }
+
+    if let Some(children) = node.children() {
+        properties.extend(convert_kdl_document(children)?);
+    }
+
+    let config = match (arguments.len(), properties.is_empty()) {
+        (0, true) => Value::Null,
+        (0, false) => Value::Object(properties),
+        (1, true) => arguments.remove(0),
+        (_, true) => Value::Array(arguments),
+        (_, false) => anyhow::bail!(
+            "KDL node `{}` mixes arguments with properties or children",
+            node.name().value()
+        ),
+    };
+
+    Ok(config)
+}
+
+#[cfg(feature = "kdl")]
+fn convert_kdl_value(value: &kdl::KdlValue) -> Value {
+    if let Some(value) = value.as_string() {
+        return Value::from(value);
+    }
+    if let Some(value) = value.as_i64() {
+        return Value::from(value);
+    }
+    if let Some(value) = value.as_f64() {
+        return Value::from(value);
+    }
+    if let Some(value) = value.as_bool() {
+        return Value::Bool(value);
+    }
+
+    Value::Null
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn detect_format_from_path() {
+        assert_eq!(Format::from_path("config.json"), Some(Format::Json));
+        assert_eq!(Format::from_path("config.yml"), Some(Format::Yaml));
+        assert_eq!(Format::from_path("config.yaml.tera"), Some(Format::Yaml));
+        assert_eq!(Format::from_path("config.json.hbs"), Some(Format::Json));
+        assert_eq!(Format::from_path("config.yaml.j2"), Some(Format::Yaml));
+        assert_eq!(Format::from_path("config.json.age"), Some(Format::Json));
+        assert_eq!(Format::from_path("config.json.gz"), Some(Format::Json));
+        assert_eq!(Format::from_path("config.toml.zst.age"), Some(Format::Toml));
+        assert_eq!(Format::from_path("Cargo.toml"), Some(Format::Toml));
+        assert_eq!(Format::from_path(".env"), Some(Format::Dotenv));
+        assert_eq!(
+            Format::from_path("deploy/.env.production"),
+            Some(Format::Dotenv)
+        );
+        assert_eq!(Format::from_path("config"), None);
+    }
+
+    #[test]
+    fn detect_format_from_contents() {
+        assert_eq!(Format::detect(b"{\"key1\": 1}"), Format::Json);
+        assert_eq!(
+            Format::detect(b"{\n  // comment\n  \"key1\": 1\n}"),
+            Format::Jsonc
+        );
+        assert_eq!(Format::detect(b"[training]\nlr = 0.1\n"), Format::Toml);
+        assert_eq!(Format::detect(b"{key1: 1,}"), Format::Json5);
+        assert_eq!(Format::detect(b"key1: 1\nkey2: udon\n"), Format::Yaml);
+        assert_eq!(
+            Format::detect(b"<config><lr>0.1</lr></config>"),
+            Format::Xml
+        );
+        #[cfg(feature = "msgpack")]
+        assert_eq!(
+            Format::detect(&rmp_serde::to_vec(&json!({"key1": 1})).unwrap()),
+            Format::MessagePack
+        );
+    }
+
+    #[test]
+    fn parse_yaml() {
+        let contents = "key1: 1\nkey2: udon\nkey3:\n  - 1\n  - 2\nkey4:\n  k1: 3\n";
+        assert_eq!(
+            parse(contents.as_bytes(), Format::Yaml).unwrap(),
+            json!({"key1": 1, "key2": "udon", "key3": [1, 2], "key4": {"k1": 3}})
+        );
+    }
+
+    #[test]
+    fn parse_toml() {
+        let contents = "key1 = 1\nkey2 = \"udon\"\nkey3 = [1, 2]\n\n[key4]\nk1 = 3\n";
+        assert_eq!(
+            parse(contents.as_bytes(), Format::Toml).unwrap(),
+            json!({"key1": 1, "key2": "udon", "key3": [1, 2], "key4": {"k1": 3}})
+        );
+    }
+
+    #[test]
+    fn parse_json5() {
+        let contents = "{\n  // comment\n  key1: 1,\n  'key2': 'udon',\n  key3: [1, 2,],\n}\n";
+        assert_eq!(
+            parse(contents.as_bytes(), Format::Json5).unwrap(),
+            json!({"key1": 1, "key2": "udon", "key3": [1, 2]})
+        );
+    }
+
+    #[cfg(feature = "hcl")]
+    #[test]
+    fn parse_hcl() {
+        let contents = "seed = 1\n\ntraining \"resnet\" {\n  lr = 0.1\n}\n";
+        assert_eq!(
+            parse(contents.as_bytes(), Format::Hcl).unwrap(),
+            json!({"seed": 1, "training": {"resnet": {"lr": 0.1}}})
+        );
+    }
+
+    #[cfg(feature = "ron")]
+    #[test]
+    fn parse_ron() {
+        let contents = "(key1: 1, key2: \"udon\", key3: [1, 2], key4: (k1: 3))";
+        assert_eq!(
+            parse(contents.as_bytes(), Format::Ron).unwrap(),
+            json!({"key1": 1, "key2": "udon", "key3": [1, 2], "key4": {"k1": 3}})
+        );
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn parse_message_pack() {
+        let config = json!({"key1": 1, "key2": "udon", "key3": [1, 2], "key4": {"k1": 3}});
+        let contents = rmp_serde::to_vec(&config).unwrap();
+        assert_eq!(parse(&contents, Format::MessagePack).unwrap(), config);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn parse_cbor() {
+        let config = json!({"key1": 1, "key2": "udon", "key3": [1, 2], "key4": {"k1": 3}});
+        let mut contents = Vec::new();
+        ciborium::ser::into_writer(&config, &mut contents).unwrap();
+        assert_eq!(parse(&contents, Format::Cbor).unwrap(), config);
+    }
+
+    #[test]
+    fn parse_nested_text_with_numbers() {
+        let contents = "epochs: 10\nlr: 0.1\nid: 007\nscale: 1.0\nname: resnet\n";
+        assert_eq!(
+            parse(contents.as_bytes(), Format::NestedText).unwrap(),
+            json!({"epochs": 10, "lr": 0.1, "id": "007", "scale": "1.0", "name": "resnet"})
+        );
+    }
+
+    #[cfg(feature = "plist")]
+    #[test]
+    fn parse_plist() {
+        let contents = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>key1</key><integer>1</integer>
+    <key>key2</key><string>udon</string>
+    <key>key3</key><array><integer>1</integer><integer>2</integer></array>
+    <key>key4</key><dict><key>k1</key><real>0.5</real></dict>
+</dict>
+</plist>"#;
+        assert_eq!(
+            parse(contents.as_bytes(), Format::Plist).unwrap(),
+            json!({"key1": 1, "key2": "udon", "key3": [1, 2], "key4": {"k1": 0.5}})
+        );
+    }
+
+    #[cfg(feature = "kdl")]
+    #[test]
+    fn parse_kdl() {
+        let contents = "seed 1\nname \"udon\"\nlayers 1 2\nserver host=\"localhost\" {\n    port 8080\n}\nhost \"a\"\nhost \"b\"\nverbose\n";
+        assert_eq!(
+            parse(contents.as_bytes(), Format::Kdl).unwrap(),
+            json!({
+                "seed": 1,
+                "name": "udon",
+                "layers": [1, 2],
+                "server": {"host": "localhost", "port": 8080},
+                "host": ["a", "b"],
+                "verbose": null
+            })
+        );
+    }
+
+    #[test]
+    fn parse_csv_documents() {
+        let contents = "lr,batchsize,out\n0.1,32,logs/a\n0.01,,logs/b\n";
+        assert_eq!(
+            parse_documents(contents.as_bytes(), Format::Csv).unwrap(),
+            vec![
+                json!({"lr": "0.1", "batchsize": "32", "out": "logs/a"}),
+                json!({"lr": "0.01", "out": "logs/b"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_json_lines_documents() {
+        let contents =
+            "{\"lr\": 0.1, \"out\": \"logs/a\"}\n\n{\"lr\": 0.01, \"out\": \"logs/b\"}\n";
+        assert_eq!(
+            parse_documents(contents.as_bytes(), Format::JsonLines).unwrap(),
+            vec![
+                json!({"lr": 0.1, "out": "logs/a"}),
+                json!({"lr": 0.01, "out": "logs/b"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_yaml_documents() {
+        let contents = "lr: 0.1\nout: logs/a\n---\nlr: 0.01\nout: logs/b\n";
+        assert_eq!(
+            parse_documents(contents.as_bytes(), Format::Yaml).unwrap(),
+            vec![
+                json!({"lr": 0.1, "out": "logs/a"}),
+                json!({"lr": 0.01, "out": "logs/b"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_empty_yaml_documents() {
+        assert_eq!(
+            parse_documents(b"# nothing yet\n", Format::Yaml).unwrap(),
+            vec![json!({})]
+        );
+    }
+}
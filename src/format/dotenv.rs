@@ -0,0 +1,81 @@
+use serde_json::{Map, Value};
+
+/// Parses a dotenv file. Every `KEY=VALUE` line becomes a top-level key, lowercased
+/// so that `BATCH_SIZE=32` is emitted as `--batch_size 32`.
+pub fn parse(contents: &str) -> anyhow::Result<Value> {
+    let mut root = Map::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("line {}: expected `KEY=VALUE`", index + 1))?;
+
+        let value = parse_value(value.trim())
+            .map_err(|error| anyhow::anyhow!("line {}: {error}", index + 1))?;
+        root.insert(key.trim().to_lowercase(), Value::String(value));
+    }
+
+    Ok(Value::Object(root))
+}
+
+/// Fails when a quoted value has no closing quote.
+fn parse_value(value: &str) -> Result<String, &'static str> {
+    if let Some(inner) = value.strip_prefix('\'') {
+        return match inner.split_once('\'') {
+            Some((inner, _)) => Ok(inner.to_string()),
+            None => Err("unterminated single quote"),
+        };
+    }
+
+    if let Some(inner) = value.strip_prefix('"') {
+        let mut result = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Ok(result),
+                '\\' => match chars.next() {
+                    Some('n') => result.push('\n'),
+                    Some('t') => result.push('\t'),
+                    Some(c) => result.push(c),
+                    None => result.push('\\'),
+                },
+                _ => result.push(c),
+            }
+        }
+        return Err("unterminated double quote");
+    }
+
+    match value.find(" #") {
+        Some(position) => Ok(value[..position].trim_end().to_string()),
+        None => Ok(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_dotenv() {
+        let contents = "# comment\nBATCH_SIZE=32\nexport NAME=\"resnet 50\"\nOUT='logs/$RUN' # output\nLR=0.1 # learning rate\n";
+        assert_eq!(
+            parse(contents).unwrap(),
+            json!({"batch_size": "32", "name": "resnet 50", "out": "logs/$RUN", "lr": "0.1"})
+        );
+    }
+
+    #[test]
+    fn parse_unterminated_quote() {
+        let error = parse("NAME=\"resnet 50\nLR=0.1\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 1: unterminated double quote");
+        let error = parse("LR=0.1\nOUT='logs/a\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: unterminated single quote");
+    }
+}
@@ -0,0 +1,77 @@
+use serde_json::{Map, Value};
+
+/// Parses an INI document. Keys before the first section are top-level keys and
+/// every `[section]` becomes a nested object. Values are kept as strings.
+pub fn parse(contents: &str) -> anyhow::Result<Value> {
+    let mut root = Map::new();
+    let mut section: Option<String> = None;
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name
+                .strip_suffix(']')
+                .ok_or_else(|| anyhow::anyhow!("line {}: unterminated section", index + 1))?
+                .trim()
+                .to_string();
+            root.entry(name.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            section = Some(name);
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .or_else(|| line.split_once(':'))
+            .ok_or_else(|| anyhow::anyhow!("line {}: expected `key = value`", index + 1))?;
+        let value = Value::String(unquote(value.trim()).to_string());
+
+        let table = match &section {
+            Some(name) => match root.get_mut(name) {
+                Some(Value::Object(table)) => table,
+                _ => anyhow::bail!("line {}: `{name}` is not a section", index + 1),
+            },
+            None => &mut root,
+        };
+        table.insert(key.trim().to_string(), value);
+    }
+
+    Ok(Value::Object(root))
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|value| value.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_sections_as_nested_objects() {
+        let contents = "; comment\nseed = 1\n\n[training]\nlr=0.1\nname = \"resnet 50\"\n";
+        assert_eq!(
+            parse(contents).unwrap(),
+            json!({"seed": "1", "training": {"lr": "0.1", "name": "resnet 50"}})
+        );
+    }
+
+    #[test]
+    fn parse_invalid_line() {
+        assert!(parse("[training]\nlr\n").is_err());
+    }
+}
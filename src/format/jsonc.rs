@@ -0,0 +1,65 @@
+/// Removes `//` and `/* */` comments outside of string literals. Newlines inside
+/// comments are kept so that parse errors still point at the original line.
+pub fn strip_comments(contents: &str) -> String {
+    let mut result = String::with_capacity(contents.len());
+    let mut chars = contents.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            result.push(c);
+            match c {
+                '\\' => result.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                result.push(c);
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        result.push(c);
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = None;
+                for c in chars.by_ref() {
+                    if previous == Some('*') && c == '/' {
+                        break;
+                    }
+                    if c == '\n' {
+                        result.push(c);
+                    }
+                    previous = Some(c);
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_line_and_block_comments() {
+        let contents =
+            "{\n  // comment\n  \"key1\": 1, /* block\n comment */ \"key2\": \"a//b\"\n}";
+        assert_eq!(
+            strip_comments(contents),
+            "{\n  \n  \"key1\": 1, \n \"key2\": \"a//b\"\n}"
+        );
+    }
+}
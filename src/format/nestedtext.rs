@@ -0,0 +1,217 @@
+use serde_json::{Map, Value};
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Parses a [NestedText](https://nestedtext.org/) document. Every leaf value is a
+/// string; numbers are recovered afterwards by `coerce_numbers`.
+pub fn parse(contents: &str) -> anyhow::Result<Value> {
+    let lines: Vec<Line> = contents
+        .lines()
+        .enumerate()
+        .filter_map(|(index, raw)| {
+            let text = raw.trim_start_matches(' ');
+            if text.trim().is_empty() || text.starts_with('#') {
+                return None;
+            }
+            Some(Line {
+                number: index + 1,
+                indent: raw.len() - text.len(),
+                text,
+            })
+        })
+        .collect();
+
+    let Some(first) = lines.first().copied() else {
+        return Ok(Value::Null);
+    };
+
+    let mut parser = Parser { lines, position: 0 };
+    let value = parser.parse_value(first.indent)?;
+    if let Some(line) = parser.lines.get(parser.position) {
+        anyhow::bail!("line {}: invalid indentation", line.number);
+    }
+
+    Ok(value)
+}
+
+#[derive(Clone, Copy)]
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    text: &'a str,
+}
+
+struct Parser<'a> {
+    lines: Vec<Line<'a>>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn current(&self, indent: usize) -> Option<Line<'a>> {
+        self.lines
+            .get(self.position)
+            .copied()
+            .filter(|line| line.indent == indent)
+    }
+
+    fn parse_value(&mut self, indent: usize) -> anyhow::Result<Value> {
+        let line = self.lines[self.position];
+        if line.text == "-" || line.text.starts_with("- ") {
+            return self.parse_list(indent);
+        }
+        if line.text == ">" || line.text.starts_with("> ") {
+            return self.parse_string(indent);
+        }
+        if line.text.starts_with('[') || line.text.starts_with('{') {
+            self.position += 1;
+            return parse_inline(line.text)
+                .ok_or_else(|| anyhow::anyhow!("line {}: invalid inline value", line.number));
+        }
+
+        self.parse_dict(indent)
+    }
+
+    /// Parses the indented block following a `-` or `key:` line with no value.
+    fn parse_nested(&mut self, indent: usize) -> anyhow::Result<Value> {
+        match self.lines.get(self.position) {
+            Some(next) if next.indent > indent => self.parse_value(next.indent),
+            _ => Ok(Value::String(String::new())),
+        }
+    }
+
+    fn parse_list(&mut self, indent: usize) -> anyhow::Result<Value> {
+        let mut items = Vec::new();
+        while let Some(line) = self.current(indent) {
+            let rest = match line.text.strip_prefix('-') {
+                Some("") => "",
+                Some(rest) if rest.starts_with(' ') => &rest[1..],
+                _ => anyhow::bail!("line {}: expected a list item", line.number),
+            };
+            self.position += 1;
+
+            if rest.is_empty() {
+                items.push(self.parse_nested(indent)?);
+            } else {
+                items.push(Value::String(rest.to_string()));
+            }
+        }
+
+        Ok(Value::Array(items))
+    }
+
+    fn parse_dict(&mut self, indent: usize) -> anyhow::Result<Value> {
+        let mut object = Map::new();
+        while let Some(line) = self.current(indent) {
+            let (key, rest) = match line.text.split_once(": ") {
+                Some((key, rest)) => (key, rest),
+                None => match line.text.strip_suffix(':') {
+                    Some(key) => (key, ""),
+                    None => anyhow::bail!("line {}: expected a dictionary item", line.number),
+                },
+            };
+            self.position += 1;
+
+            let value = if rest.is_empty() {
+                self.parse_nested(indent)?
+            } else {
+                Value::String(rest.to_string())
+            };
+            object.insert(key.trim_end().to_string(), value);
+        }
+
+        Ok(Value::Object(object))
+    }
+
+    fn parse_string(&mut self, indent: usize) -> anyhow::Result<Value> {
+        let mut lines = Vec::new();
+        while let Some(line) = self.current(indent) {
+            let text = match line.text.strip_prefix('>') {
+                Some("") => "",
+                Some(rest) if rest.starts_with(' ') => &rest[1..],
+                _ => anyhow::bail!("line {}: expected a string item", line.number),
+            };
+            self.position += 1;
+            lines.push(text);
+        }
+
+        Ok(Value::String(lines.join("\n")))
+    }
+}
+
+fn parse_inline(text: &str) -> Option<Value> {
+    let mut chars = text.chars().peekable();
+    let value = parse_inline_value(&mut chars, &[])?;
+    chars.next().is_none().then_some(value)
+}
+
+fn parse_inline_value(chars: &mut Peekable<Chars>, terminators: &[char]) -> Option<Value> {
+    while chars.next_if_eq(&' ').is_some() {}
+
+    let value = match chars.peek() {
+        Some('[') => {
+            chars.next();
+            let mut items = Vec::new();
+            if chars.next_if_eq(&']').is_none() {
+                loop {
+                    items.push(parse_inline_value(chars, &[',', ']'])?);
+                    if chars.next()? == ']' {
+                        break;
+                    }
+                }
+            }
+            Value::Array(items)
+        }
+        Some('{') => {
+            chars.next();
+            let mut object = Map::new();
+            if chars.next_if_eq(&'}').is_none() {
+                loop {
+                    let key: String = std::iter::from_fn(|| chars.next_if(|c| *c != ':')).collect();
+                    chars.next()?;
+                    object.insert(
+                        key.trim().to_string(),
+                        parse_inline_value(chars, &[',', '}'])?,
+                    );
+                    if chars.next()? == '}' {
+                        break;
+                    }
+                }
+            }
+            Value::Object(object)
+        }
+        _ => {
+            let text: String =
+                std::iter::from_fn(|| chars.next_if(|c| !terminators.contains(c))).collect();
+            Value::String(text.trim().to_string())
+        }
+    };
+
+    while chars.next_if_eq(&' ').is_some() {}
+
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_nested_text() {
+        let contents = "# comment\nname: resnet 50\ntraining:\n    lr: 0.1\n    layers:\n        - 1\n        - 2\ndescription:\n    > first line\n    > second line\nhosts:\n    [a, b]\n";
+        assert_eq!(
+            parse(contents).unwrap(),
+            json!({
+                "name": "resnet 50",
+                "training": {"lr": "0.1", "layers": ["1", "2"]},
+                "description": "first line\nsecond line",
+                "hosts": ["a", "b"]
+            })
+        );
+    }
+
+    #[test]
+    fn parse_invalid_indentation() {
+        assert!(parse("key1: 1\n    key2: 2\n").is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use serde_json::{Map, Value};
+
+/// Parses a Java `.properties` file. Dotted keys are treated as nested paths, so
+/// `server.port=8080` becomes `{"server": {"port": "8080"}}`.
+pub fn parse(contents: &str) -> anyhow::Result<Value> {
+    let mut root = Map::new();
+
+    for line in logical_lines(contents) {
+        let (key, value) = split_key_value(&line);
+        insert_path(&mut root, &unescape(key), Value::String(unescape(value)))?;
+    }
+
+    Ok(Value::Object(root))
+}
+
+/// Joins lines ending with an odd number of backslashes with the following line and
+/// drops blank lines and comments.
+fn logical_lines(contents: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current: Option<String> = None;
+
+    for line in contents.lines() {
+        let line = line.trim_start();
+        if current.is_none() && (line.is_empty() || line.starts_with('#') || line.starts_with('!'))
+        {
+            continue;
+        }
+
+        let backslashes = line.len() - line.trim_end_matches('\\').len();
+        let continued = backslashes % 2 == 1;
+        let line = if continued {
+            &line[..line.len() - 1]
+        } else {
+            line
+        };
+
+        let mut logical = current.take().unwrap_or_default();
+        logical.push_str(line);
+        if continued {
+            current = Some(logical);
+        } else {
+            lines.push(logical);
+        }
+    }
+    lines.extend(current);
+
+    lines
+}
+
+fn split_key_value(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    for (position, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '=' | ':' | ' ' | '\t' => {
+                let mut value = line[position + 1..].trim_start();
+                if c.is_whitespace() {
+                    if let Some(rest) = value.strip_prefix(['=', ':']) {
+                        value = rest.trim_start();
+                    }
+                }
+                return (&line[..position], value);
+            }
+            _ => {}
+        }
+    }
+
+    (line, "")
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('u') => {
+                let code: String = chars.by_ref().take(4).collect();
+                match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                    Some(c) => result.push(c),
+                    None => result.push_str(&code),
+                }
+            }
+            Some(c) => result.push(c),
+            None => {}
+        }
+    }
+
+    result
+}
+
+/// Inserts `value` at the dotted `key`. A repeated key keeps the last value, as Java
+/// does, but a key used both as a value and as a prefix of other keys is an error.
+fn insert_path(root: &mut Map<String, Value>, key: &str, value: Value) -> anyhow::Result<()> {
+    let conflict = || anyhow::anyhow!("`{key}` conflicts with another key");
+    let path: Vec<&str> = key.split('.').collect();
+    let (last, parents) = path.split_last().ok_or_else(conflict)?;
+
+    let mut current = root;
+    for parent in parents {
+        current = current
+            .entry(parent.to_string())
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or_else(conflict)?;
+    }
+
+    if current.get(*last).is_some_and(Value::is_object) {
+        return Err(conflict());
+    }
+    current.insert(last.to_string(), value);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_dotted_keys_as_nested_objects() {
+        let contents = "# comment\nserver.port=8080\nserver.host : localhost\nname resnet\\\n  50\npath=C\\:\\\\data\n";
+        assert_eq!(
+            parse(contents).unwrap(),
+            json!({"server": {"port": "8080", "host": "localhost"}, "name": "resnet50", "path": "C:\\data"})
+        );
+    }
+
+    #[test]
+    fn parse_conflicting_keys() {
+        assert!(parse("server=1\nserver.port=8080\n").is_err());
+        assert!(parse("server.port=8080\nserver=1\n").is_err());
+    }
+
+    #[test]
+    fn parse_duplicate_keys() {
+        assert_eq!(
+            parse("server.port=8080\nserver.port=9090\n").unwrap(),
+            json!({"server": {"port": "9090"}})
+        );
+    }
+}
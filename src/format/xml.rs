@@ -0,0 +1,85 @@
+use serde_json::{Map, Value};
+
+/// Parses an XML document. The root element is only a container: its attributes
+/// and child elements become the top-level keys. Repeated elements are collected
+/// into an array. The text of an element which also has attributes or children
+/// is ignored, since it has no key to become an option under.
+pub fn parse(contents: &str) -> anyhow::Result<Value> {
+    let document = roxmltree::Document::parse(contents)?;
+
+    Ok(convert_element(document.root_element()))
+}
+
+fn convert_element(node: roxmltree::Node) -> Value {
+    let mut object = Map::new();
+    for attribute in node.attributes() {
+        object.insert(
+            attribute.name().to_string(),
+            Value::String(attribute.value().to_string()),
+        );
+    }
+
+    let mut text = String::new();
+    for child in node.children() {
+        if child.is_text() {
+            text.push_str(child.text().unwrap_or_default());
+            continue;
+        }
+
+        if !child.is_element() {
+            continue;
+        }
+
+        let name = child.tag_name().name().to_string();
+        let value = convert_element(child);
+        match object.get_mut(&name) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(existing) => {
+                let first = existing.take();
+                *existing = Value::Array(vec![first, value]);
+            }
+            None => {
+                object.insert(name, value);
+            }
+        }
+    }
+
+    let text = text.trim();
+    if object.is_empty() {
+        if text.is_empty() {
+            return Value::Null;
+        }
+        return Value::String(text.to_string());
+    }
+
+    Value::Object(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_elements_and_attributes() {
+        let contents = r#"<config seed="1">
+            <training><lr>0.1</lr></training>
+            <host>a</host>
+            <host>b</host>
+            <verbose/>
+        </config>"#;
+        assert_eq!(
+            parse(contents).unwrap(),
+            json!({"seed": "1", "training": {"lr": "0.1"}, "host": ["a", "b"], "verbose": null})
+        );
+    }
+
+    #[test]
+    fn parse_mixed_content() {
+        let contents = r#"<config><model name="resnet">large<depth>50</depth></model></config>"#;
+        assert_eq!(
+            parse(contents).unwrap(),
+            json!({"model": {"name": "resnet", "depth": "50"}})
+        );
+    }
+}
@@ -1,9 +1,250 @@
+mod args;
+mod cli;
+mod decrypt;
+mod format;
+mod merge;
+mod order;
+mod output;
+mod preset;
+mod select;
+mod shell;
+mod source;
+mod template;
+mod units;
+mod vault;
+
+use args::{Arg, ArgOptions, ArgsPosition, ArrayStyle, NestedArrays, NullPolicy};
+use format::Format;
+use merge::MergeOptions;
+use output::Output;
 use serde_json::Value;
 #[cfg(test)]
 use serde_json::json;
+use std::collections::HashMap;
 use std::env;
-use std::fs::File;
-use std::io::prelude::*;
+use std::path::Path;
+use template::{Engine, Templates};
+
+const USAGE: &str = "usage: config2args [OPTIONS] CONFIG...
+
+CONFIG is a path to a config file, an http(s):// URL, an s3:// or gs:// URI
+(with the `cloud` feature), a consul://host/key or etcd://host/key key (or
+consul+https:// and etcd+https:// over TLS), a k8s://namespace/configmap/key
+ConfigMap key (kubectl must be in PATH), a file at a git ref like
+git://path/to/repo#REF:path/to/config.json, or `-` to read the config from stdin.
+Configs encrypted by SOPS are decrypted with the `sops` command, and configs
+encrypted by age with the `age` command. gzip and zstd compressed configs are
+decompressed.
+CONFIG2ARGS_BEARER_TOKEN is sent as a bearer token to http(s):// URLs.
+When several configs are given, later ones are deep-merged over earlier ones.
+A glob pattern like 'configs/*.json' is expanded into the matching files in
+lexical order.
+
+Values are quoted for POSIX shells, so the output can be passed to `eval`. Args
+generated from a .tera template are not quoted, except for the values with
+whitespace, quotes or backslashes and without template tags, unless the values
+are evaluated one by one with `--template-values`. Templates are never
+HTML-escaped.
+
+OPTIONS:
+    --format FORMAT
+        Format of the configs: json, jsonc, json5, yaml, toml, ini, xml, hcl,
+        dotenv, properties, ron, msgpack, cbor, nestedtext, plist, kdl, csv, tsv
+        or jsonl (default: guessed from the file extension or the contents)
+    --stdin
+        Read the config from stdin (same as `-`)
+    --profile NAME
+        Merge the profile NAME in `_profiles` over the config
+    --defaults FILE
+        Fill in the keys missing or null in the config with the values in FILE
+    --array-merge [KEY=]STRATEGY
+        Merge arrays (or only the array at KEY) with STRATEGY: replace
+        (default), concat or union
+    --set KEY=VALUE
+        Set the value at the dotted key path KEY. VALUE is parsed as JSON, or
+        used as a string when it is not valid JSON. Can be given multiple times
+    --env-prefix PREFIX
+        Override values with environment variables named PREFIX__KEY__PATH,
+        like `C2A__TRAINING__LR=0.01` for `training.lr`. Keys are matched
+        ignoring case, like `training.learningRate` for
+        `C2A__TRAINING__LEARNINGRATE`. `--set` takes precedence
+    --resolve-vault
+        Replace `vault:PATH#FIELD` values with the secrets in Vault, read from
+        VAULT_ADDR with VAULT_TOKEN
+    --age-identity FILE
+        Decrypt age-encrypted configs with the identity FILE (default:
+        CONFIG2ARGS_AGE_IDENTITY)
+    --no-quote
+        Print the values as they are, without quoting them for the shell
+    --style STYLE
+        Print the options in STYLE: gnu (`--key value`, default), equals
+        (`--key=value`), single-dash (`-key value`), windows (`/key value`),
+        windows-colon (`/key:value`) or jvm (`-Dkey=value`)
+    --always-long
+        Prefix every flag with `--`, even for single-character keys
+    --always-short
+        Prefix every flag with `-`, even for multi-character keys
+    --separator SEPARATOR
+        Join the keys of nested objects with SEPARATOR (default: `.`)
+    --key-case CASE
+        Rewrite the keys in CASE before printing them as flags: kebab
+        (`batch-size`), snake (`batch_size`), camel (`batchSize`) or preserve
+        (default)
+    --var NAME=VALUE
+        Set the variable at the dotted path NAME in the context of the templates,
+        over the values of the config. VALUE is parsed as JSON, or used as a
+        string when it is not valid JSON. Can be given multiple times
+    --template FILE
+        Print the template in FILE rendered with the config as its context,
+        like `train.py --lr {{ lr }}`, instead of the generated args. With
+        `--template-dir`, FILE is the path of a template in the directory
+    --template-values
+        Evaluate every string value of the config as its own template, so
+        that its output is quoted as a single arg
+    --template-dir DIR
+        Load the templates under DIR, so that the templates can include and
+        extend each other
+    --engine ENGINE
+        Render the templates with ENGINE: tera, handlebars or minijinja (with
+        the feature of the same name). A .tera, .hbs or .j2 config is a template,
+        and ENGINE defaults to the one of the extension of the template given
+        with `--template` or of the config, or else tera
+    --rename-map FILE
+        Rename the keys of the config by the map in FILE, like
+        {\"learning_rate\": \"lr\", \"model.layers\": \"depth\"}
+    --sort SORT
+        Print the options in the order of SORT: spec (the `_order` array of
+        the config, then the order of the config, default), input (the order
+        of the config) or alpha (the alphabetical order of the keys)
+    --args-position POSITION
+        Print the positional args of the `_args` key before or after (default)
+        the options
+    --array-style [KEY=]STYLE
+        Print the values of arrays, or of the array at the dotted KEY, in
+        STYLE: space (`--key 1 2`, default) or repeat (`--key 1 --key 2`)
+    --array-delimiter DELIMITER
+        Join the values of arrays with DELIMITER into one value, like
+        `--key 1,2,3` for `,`
+    --nested-arrays MODE
+        Print the arrays of objects in MODE: group (repeat the flags of every
+        object, default) or indexed (`--hosts.0.name a`). indexed also
+        supports arrays of arrays, like `--key.1.0 4`, and nulls in an array
+    --bare-prefix PREFIX
+        Print the values of the keys starting with PREFIX without a flag
+        (default: `_`). An empty PREFIX prints every key as a flag, and a key
+        escaped with a backslash like `\\_key` is printed as `--_key`
+    --float-format FORMAT
+        Print the numbers which are not integers in FORMAT: shortest (the
+        shortest digits which parse back to the same number, default),
+        fixed:N (N digits after the decimal point) or scientific (`1e-5`)
+    --negative-equals
+        Join a value starting with `-` to its flag, like `--key=-5`, so that
+        it is not taken for a flag
+    --count KEYS
+        Print the comma-separated dotted KEYS holding integers as their flags
+        repeated, like `-vvv` for `{\"v\": 3}`, up to 100 times. Can be given
+        multiple times
+    --dedup POLICY
+        Print the options with the same flag, like the keys renamed to the same
+        flag, by POLICY: keep (every option, default), last, first or error
+    --parse-durations
+        Convert the strings holding durations, like `5m` or `1h30m`, into numbers
+        of seconds. A key with `\"$type\": \"duration\"` is always converted
+    --duration-unit UNIT
+        Convert the durations into UNIT: s (seconds, default) or ms
+    --parse-sizes KEYS
+        Convert the sizes at the comma-separated dotted KEYS, like `2GiB` or
+        `512k`, into numbers of bytes. A key with `\"$type\": \"size\"` is
+        converted as well. Can be given multiple times
+    --explicit-booleans
+        Print booleans as values like `--verbose true` instead of printing the
+        flag for `true` and omitting it for `false`
+    --negate-false
+        Print `false` as a negated flag like `--no-verbose` instead of omitting
+        it
+    --null-policy POLICY
+        Print null values by POLICY: skip-pair (omit the flag, default),
+        flag-only (`--key`), literal-null (`--key null`) or error
+    --preset PRESET
+        Map the well-known sections of the config to the flags of a tool.
+        docker maps `env`, `volumes`, `ports` and `labels` to the `-e`, `-v`,
+        `-p` and `--label` flags of `docker run`
+    --pretty
+        Print every option on its own line, continued with a backslash
+    -o, --output FILE
+        Write the args to FILE instead of stdout. FILE is replaced atomically
+        by renaming a temporary file
+    --output-format OUTPUT
+        Print the args as OUTPUT:
+            shell       args quoted for the shell (default)
+            print0      every arg terminated by NUL
+            lines       every arg on its own line
+            json        a JSON array
+            k8s-args    an `args: [...]` YAML fragment for a Pod spec
+            powershell  a hashtable for splatting
+            env-export  `export KEY=VALUE` lines
+            env-file    `KEY=VALUE` lines for `docker --env-file`
+            systemd     an `ExecStart=` line for a systemd unit
+            github-with a `with:` mapping for the inputs of a GitHub Actions step
+            makefile    an `ARGS := ...` Makefile variable
+            rust        a Rust `&[&str]` literal
+            c           a C `char *[]` compound literal terminated by NULL
+        Environment variable names are the key paths uppercased and joined
+        with `_`
+    --program PROGRAM
+        Program run with the args in the systemd output and the script of
+        `--emit-script` (default: the `_program` key of the config)
+    --emit-script FILE
+        Write an executable shell script which runs the program with the args
+        to FILE, instead of printing the args. Cannot be used with `--output`
+    --print0
+        Print every arg terminated by NUL instead of a line, for `xargs -0`
+        (same as `--output-format print0`)
+    --lines
+        Print every arg on its own line without quoting it (same as
+        `--output-format lines`)
+    --path POINTER
+        Generate the args only from the subtree at the JSON Pointer POINTER,
+        like /training/optimizer
+    --query FILTER
+        Transform the config with the jq FILTER before generating the args.
+        Every output of FILTER produces a line, like `.jobs[]`
+    --jmespath EXPRESSION
+        Generate the args from the result of the JMESPath EXPRESSION, like
+        jobs[0].params
+    --exclude KEYS
+        Drop the comma-separated dotted KEYS, which may be glob patterns like
+        `debug.*`, from the config. Can be given multiple times
+    --require KEYS
+        Fail unless the config has the comma-separated dotted KEYS, like
+        `data.path`, in addition to the keys listed in its `_required` array. Can
+        be given multiple times
+    --only KEYS
+        Keep only the comma-separated dotted KEYS, which may be glob patterns
+        like `training.*`, in the config. Can be given multiple times
+";
+
+/// Reserved key holding the path to a parent config, relative to the config itself.
+const EXTENDS_KEY: &str = "_extends";
+
+/// Reserved key holding the positional args, which are printed before or after all
+/// the options.
+const ARGS_KEY: &str = "_args";
+
+/// Reserved key holding the args passed through to a child process, which are printed
+/// last after a `--` separator.
+const TRAILING_KEY: &str = "_trailing";
+
+/// Reserved key holding fragments printed verbatim, without being quoted or turned into
+/// options.
+const RAW_KEY: &str = "_raw";
+
+/// Directive holding the value of a key with `$` directives, like
+/// `{"$value": 0.1, "$flag": "-l"}`.
+const VALUE_DIRECTIVE: &str = "$value";
+
+/// Name of the environment variables in the context of a template.
+const ENV_CONTEXT_KEY: &str = "env";
 
 fn main() -> anyhow::Result<()> {
     let args: Vec<String> = env::args().skip(1).collect();
@@ -12,211 +253,1512 @@ fn main() -> anyhow::Result<()> {
         std::process::exit(1);
     }
 
-    let config_file_path = &args[0];
-    let config = parse_json_file(config_file_path)?;
+    let mut options = cli::parse_args(&args)?;
+    if let Some(path) = &options.rename_map {
+        options.args.renames = load_rename_map(path)?;
+    }
+    let env_overrides = match &options.env_prefix {
+        Some(prefix) => merge::env_overrides(prefix, env_vars()),
+        None => Vec::new(),
+    };
 
-    let raw_string = generate_args_string(&config, None);
+    let age_identity = options
+        .age_identity
+        .clone()
+        .or_else(|| env::var(decrypt::AGE_IDENTITY_ENV).ok());
+    let documents = load_configs(
+        &options.config_paths,
+        options.format,
+        &options.merge,
+        age_identity.as_deref(),
+    )?;
+    let defaults = match &options.defaults {
+        Some(path) => Some(load_defaults(
+            path,
+            &options.merge,
+            age_identity.as_deref(),
+        )?),
+        None => None,
+    };
+    let mut configs = Vec::new();
+    for mut config in documents {
+        if let Some(defaults) = &defaults {
+            merge::fill_defaults(&mut config, defaults);
+        }
+        merge::resolve_refs(&mut config)?;
+        merge::apply_profile(&mut config, options.profile.as_deref(), &options.merge)?;
+        for (path, value) in &env_overrides {
+            let path = merge::match_key_case(&config, path);
+            merge::set_path(&mut config, &path, value.clone())?;
+        }
+        for (path, value) in &options.overrides {
+            merge::set_path(&mut config, path, value.clone())?;
+        }
+        if options.resolve_vault {
+            vault::resolve_secrets(&mut config)?;
+        }
+        if let Some(pointer) = &options.path {
+            config = select::pointer(&mut config, pointer)?;
+        }
+        if let Some(expression) = &options.jmespath {
+            config = select::jmespath(&config, expression)?;
+        }
+        match &options.query {
+            Some(filter) => configs.extend(select::query(config, filter)?),
+            None => configs.push(config),
+        }
+    }
 
-    let is_tera_template = config_file_path.ends_with(".tera");
-    if is_tera_template {
-        let result = eval_as_tera_template(&raw_string)?;
-        println!("{result}");
-    } else {
-        println!("{raw_string}");
+    let is_template = options
+        .config_paths
+        .iter()
+        .any(|path| Engine::from_path(path).is_some());
+    // The output of a template is printed as it is, unless its values are evaluated one
+    // by one and generate args.
+    let is_rendered = options.template.is_some() || (is_template && !options.template_values);
+    let mut templates = Templates::new(template_engine(&options), options.template_dir.as_deref())?;
+    // A template in the template directory is already loaded in `templates` by its name.
+    let template = match (&options.template, &options.template_dir) {
+        (Some(path), None) => Some(load_template(path)?),
+        _ => None,
+    };
+    let mut rendered = String::new();
+    let mut script_commands = Vec::new();
+    for mut config in configs {
+        order::apply(&mut config, options.sort)?;
+        let mut output_options = options.output.clone();
+        // `_program` is reserved, so it is removed even when no output uses it.
+        output_options.program =
+            output::take_program(&mut config, options.output.program.as_deref())?;
+        select::require(&mut config, &options.require)?;
+        select::only(&mut config, &options.only)?;
+        select::exclude(&mut config, &options.exclude)?;
+
+        if options.template_values {
+            eval_template_values(&mut templates, &mut config, &options.vars)?;
+        }
+
+        let args = if is_rendered {
+            let result = match (&template, &options.template) {
+                (Some(template), _) => {
+                    eval_as_template(&mut templates, template, &config, &options.vars)?
+                }
+                (None, Some(name)) => render_template(&templates, name, &config, &options.vars)?,
+                (None, None) => {
+                    let raw_string = generate_args_string(&config, &options.args)?;
+                    eval_as_template(&mut templates, &raw_string, &config, &options.vars)?
+                }
+            };
+            let result = result.trim_end();
+            if output_options.format == Output::Shell
+                && !output_options.pretty
+                && options.emit_script.is_none()
+            {
+                rendered.push_str(&format!("{result}\n"));
+                continue;
+            }
+            // The template may expand into several args, separated by whitespace.
+            shell::split(&result)?.into_iter().map(Arg::Value).collect()
+        } else {
+            let groups = generate_arg_groups(&config, &[], &options.args)?;
+            let mut args = args::dedup(groups, &options.args)?;
+            if let Some(preset) = options.preset {
+                args = preset::apply(args, preset);
+            }
+            args
+        };
+
+        if options.emit_script.is_some() {
+            script_commands.push(output::script_command(
+                &args,
+                &options.args,
+                &output_options,
+            )?);
+        } else {
+            rendered.push_str(&output::render(&args, &options.args, &output_options)?);
+        }
+    }
+
+    if let Some(path) = &options.emit_script {
+        output::write_script(path, &script_commands)?;
+    }
+    match &options.output_path {
+        Some(path) => output::write_atomically(path, &rendered, false)?,
+        None => print!("{rendered}"),
     }
 
     Ok(())
 }
 
+/// Returns the engine given with `--engine`, or else the one of the extension of the
+/// template or of a template config, like MiniJinja for `config.yaml.j2`.
+fn template_engine(options: &cli::Options) -> Engine {
+    options.engine.unwrap_or_else(|| {
+        options
+            .template
+            .iter()
+            .chain(&options.config_paths)
+            .find_map(|path| Engine::from_path(path))
+            .unwrap_or_default()
+    })
+}
+
+/// Returns the environment variables whose names and values are valid UTF-8.
+fn env_vars() -> impl Iterator<Item = (String, String)> {
+    env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+}
+
 fn show_usage() {
-    println!("usage: config2args /path/to/config.json");
+    print!("{USAGE}");
 }
 
-fn parse_json_file(file_path: &str) -> anyhow::Result<Value> {
-    let mut file = File::open(file_path)?;
+/// Loads the configs to convert. Glob patterns are expanded, and several configs are
+/// deep-merged into one, in which case none of them may be a batch of configs.
+fn load_configs(
+    paths: &[String],
+    format: Option<Format>,
+    merge_options: &MergeOptions,
+    age_identity: Option<&str>,
+) -> anyhow::Result<Vec<Value>> {
+    let mut expanded_paths = Vec::new();
+    for path in paths {
+        expanded_paths.extend(source::expand_glob(path)?);
+    }
+    let paths = expanded_paths.as_slice();
 
-    let mut raw_json_contents = String::new();
-    file.read_to_string(&mut raw_json_contents)?;
+    if let [path] = paths {
+        return load_config_file(path, format, merge_options, age_identity);
+    }
 
-    let config = serde_json::from_str(&raw_json_contents)?;
+    let mut merged = Value::Null;
+    for path in paths {
+        let mut documents = load_config_file(path, format, merge_options, age_identity)?;
+        if documents.len() != 1 {
+            anyhow::bail!("{path}: a batch of configs cannot be merged with other configs");
+        }
+        merge::deep_merge(&mut merged, documents.remove(0), merge_options);
+    }
 
-    Ok(config)
+    Ok(vec![merged])
 }
 
-fn generate_args_string(config: &Value, prefix: Option<String>) -> String {
-    let mut args = String::new();
+/// Loads the defaults file, which must hold a single config.
+fn load_defaults(
+    path: &str,
+    merge_options: &MergeOptions,
+    age_identity: Option<&str>,
+) -> anyhow::Result<Value> {
+    let mut documents = load_config_file(path, None, merge_options, age_identity)?;
+    if documents.len() != 1 {
+        anyhow::bail!("{path}: a batch of configs cannot be defaults");
+    }
 
-    if config.is_object() {
-        let keys = config.as_object().unwrap().keys();
+    Ok(documents.remove(0))
+}
 
-        for key in keys {
-            let mut key_name = prefix.clone().unwrap_or_default();
-            key_name.push_str(key);
+fn load_config_file(
+    file_path: &str,
+    format: Option<Format>,
+    merge_options: &MergeOptions,
+    age_identity: Option<&str>,
+) -> anyhow::Result<Vec<Value>> {
+    load_extended_config_file(
+        file_path,
+        format,
+        merge_options,
+        age_identity,
+        &mut Vec::new(),
+    )
+}
 
-            let item = &config[key];
-            if item.is_object() {
-                key_name.push('.');
-                let nested_args = generate_args_string(item, Some(key_name.clone()));
-                args.push_str(&format!("{nested_args} "));
-                continue;
-            }
+/// Loads a config file and its `_extends` parents. `chain` holds the sources being
+/// loaded, the canonical paths of files and the URIs of the others, to detect circular
+/// `_extends`.
+fn load_extended_config_file(
+    file_path: &str,
+    format: Option<Format>,
+    merge_options: &MergeOptions,
+    age_identity: Option<&str>,
+    chain: &mut Vec<String>,
+) -> anyhow::Result<Vec<Value>> {
+    let mut raw_contents = source::read(file_path)?;
+    if decrypt::is_age_encrypted(&raw_contents) {
+        raw_contents = decrypt::age(file_path, &raw_contents, age_identity)?;
+    }
+    let raw_contents = source::decompress(file_path, raw_contents)?;
+    let format = format
+        .or_else(|| Format::from_path(file_path))
+        .unwrap_or_else(|| Format::detect(&raw_contents));
 
-            if key_name.find('_') != Some(0) {
-                if key_name.len() == 1 {
-                    args.push_str(&format!("-{key_name} "));
-                } else {
-                    args.push_str(&format!("--{key_name} "));
-                }
-            }
+    let source = if source::is_local_file(file_path) {
+        std::fs::canonicalize(file_path)?.display().to_string()
+    } else {
+        file_path.to_string()
+    };
+    if chain.contains(&source) {
+        anyhow::bail!(
+            "circular `{EXTENDS_KEY}`: {} -> {source}",
+            chain.join(" -> ")
+        );
+    }
+    chain.push(source);
 
-            if item.is_number() {
-                let value = item.as_f64().unwrap();
-                args.push_str(&format!("{value} "));
-                continue;
-            }
+    let mut documents = format::parse_documents(&raw_contents, format)?;
+    if decrypt::is_sops_encrypted(&documents) {
+        let decrypted = decrypt::sops(file_path, &raw_contents, format)?;
+        documents = format::parse_documents(&decrypted, format)?;
+    }
+
+    let documents = documents
+        .into_iter()
+        .map(|config| extend_config(config, file_path, merge_options, age_identity, chain))
+        .collect();
+
+    chain.pop();
+
+    documents
+}
+
+/// Deep-merges the config over the parent config named by its `_extends` key.
+fn extend_config(
+    mut config: Value,
+    file_path: &str,
+    merge_options: &MergeOptions,
+    age_identity: Option<&str>,
+    chain: &mut Vec<String>,
+) -> anyhow::Result<Value> {
+    let Some(parent) = config
+        .as_object_mut()
+        .and_then(|object| object.shift_remove(EXTENDS_KEY))
+    else {
+        return Ok(config);
+    };
+
+    let parent = parent
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("{file_path}: `{EXTENDS_KEY}` must be a path"))?;
+    let parent_path = parent_source(file_path, parent);
+
+    let mut parents =
+        load_extended_config_file(&parent_path, None, merge_options, age_identity, chain)?;
+    if parents.len() != 1 {
+        anyhow::bail!("{parent_path}: a batch of configs cannot be extended");
+    }
+
+    let mut extended = parents.remove(0);
+    merge::deep_merge(&mut extended, config, merge_options);
+
+    Ok(extended)
+}
+
+/// Returns the source of the parent named by `_extends`. A URI is used as it is, and a
+/// path is relative to the config.
+fn parent_source(file_path: &str, parent: &str) -> String {
+    if parent.contains("://") {
+        return parent.to_string();
+    }
+
+    Path::new(file_path)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(parent)
+        .to_string_lossy()
+        .to_string()
+}
 
-            if item.is_string() {
-                let value = item.as_str().unwrap();
-                args.push_str(&format!("{value} "));
+/// Loads a tera template rendered with the config as its context, given with
+/// `--template`.
+fn load_template(path: &str) -> anyhow::Result<String> {
+    String::from_utf8(source::read(path)?)
+        .map_err(|_| anyhow::anyhow!("{path}: the template must be UTF-8"))
+}
+
+/// Loads a map from the dotted key paths of a config to the names of the options.
+fn load_rename_map(path: &str) -> anyhow::Result<HashMap<String, String>> {
+    let contents = source::read(path)?;
+    let format = Format::from_path(path).unwrap_or_else(|| Format::detect(&contents));
+    let map = format::parse_documents(&contents, format)?;
+    let Some(Value::Object(map)) = map.into_iter().next() else {
+        anyhow::bail!("{path}: the rename map must be an object");
+    };
+
+    let mut renames = HashMap::new();
+    for (key, name) in map {
+        let Value::String(name) = name else {
+            anyhow::bail!("{path}: the new name of `{key}` must be a string");
+        };
+        renames.insert(key, name);
+    }
+
+    Ok(renames)
+}
+
+fn generate_args_string(config: &Value, options: &ArgOptions) -> anyhow::Result<String> {
+    let args = args::dedup(generate_arg_groups(config, &[], options)?, options)?;
+    let mut tokens = Vec::new();
+    for (arg, group) in args.iter().zip(args::token_groups(&args, options)) {
+        if matches!(arg, Arg::Raw(_)) {
+            tokens.extend(group);
+        } else {
+            tokens.extend(group.into_iter().map(quote_for_template));
+        }
+    }
+
+    Ok(tokens.join(" "))
+}
+
+/// Quotes a token which would be split at the whitespace or the quotes in it, unless it
+/// holds template tags, whose output may be several args.
+fn quote_for_template(token: String) -> String {
+    let is_split = token.contains(|c: char| c.is_whitespace() || c == '\'' || c == '\\');
+    if is_split && !has_template_tags(&token) {
+        shell::quote(&token).into_owned()
+    } else {
+        token
+    }
+}
+
+fn has_template_tags(text: &str) -> bool {
+    ["{{", "{%", "{#"].iter().any(|tag| text.contains(tag))
+}
+
+fn generate_args(
+    config: &Value,
+    prefix: &[String],
+    options: &ArgOptions,
+) -> anyhow::Result<Vec<Arg>> {
+    let groups = generate_arg_groups(config, prefix, options)?;
+    Ok(groups.into_iter().flatten().collect())
+}
+
+/// Generates the args of every key of a config as its own group, like the options of
+/// every item of an array in the `Repeat` style, which are deduplicated together.
+fn generate_arg_groups(
+    config: &Value,
+    prefix: &[String],
+    options: &ArgOptions,
+) -> anyhow::Result<Vec<Vec<Arg>>> {
+    let mut groups = Vec::new();
+    let mut positionals = Vec::new();
+    let mut trailing = Vec::new();
+
+    if config.is_object() {
+        for (key, item) in config.as_object().unwrap() {
+            let mut key_path = prefix.to_vec();
+            key_path.push(key.clone());
+
+            if item.get(VALUE_DIRECTIVE).is_some() {
+                groups.push(generate_directive_args(&key_path, item, options)?);
                 continue;
             }
-
-            if item.is_null() {
+            if item.is_object() {
+                groups.extend(generate_arg_groups(item, &key_path, options)?);
                 continue;
             }
+            if let Some(items) = item.as_array() {
+                let is_nested = |item: &Value| {
+                    item.is_object()
+                        || ((item.is_array() || item.is_null())
+                            && options.nested_arrays == NestedArrays::Indexed)
+                };
+                if items.iter().any(is_nested) {
+                    groups.push(generate_nested_array_args(&key_path, items, options)?);
+                    continue;
+                }
+            }
 
-            if item.is_array() {
-                let string_array = convert_vec_to_string_vec(item.as_array().unwrap());
-                let joined = string_array.join(" ");
-                args.push_str(&format!("{joined} "));
-                continue;
+            let converted;
+            let item = if options.is_size(&key_path) {
+                converted = convert_typed_value(&key_path, item.clone(), "size", options)?;
+                &converted
+            } else {
+                item
+            };
+
+            let mut negated = false;
+            let values = if item.is_boolean() && !options.explicit_booleans {
+                // `true` is a flag without a value, and `false` omits the flag unless it
+                // is negated like `--no-key`.
+                if !item.as_bool().unwrap() {
+                    if !options.negate_false || options.is_bare(&key_path[0]) {
+                        continue;
+                    }
+                    negated = true;
+                }
+                vec![]
+            } else if item.is_number() {
+                vec![number_to_string(item, options)]
+            } else if item.is_string() {
+                vec![string_to_string(item.as_str().unwrap(), options)]
+            } else if item.is_boolean() {
+                vec![item.as_bool().unwrap().to_string()]
+            } else if item.is_null() {
+                match options.null_policy {
+                    NullPolicy::SkipPair => continue,
+                    NullPolicy::FlagOnly => vec![],
+                    NullPolicy::LiteralNull => vec!["null".to_string()],
+                    NullPolicy::Error => {
+                        anyhow::bail!("{}: null values are not allowed", key_path.join("."))
+                    }
+                }
+            } else if item.is_array() {
+                convert_vec_to_string_vec(&key_path, item.as_array().unwrap(), options)?
+            } else {
+                panic!(
+                    "Only number, string, boolean, array and object are supported as an item of json config file."
+                );
+            };
+
+            if prefix.is_empty() && key == ARGS_KEY {
+                positionals.extend(values.into_iter().map(Arg::Value));
+            } else if prefix.is_empty() && key == TRAILING_KEY {
+                trailing.extend(values.into_iter().map(Arg::Value));
+            } else if prefix.is_empty() && key == RAW_KEY {
+                groups.push(values.into_iter().map(Arg::Raw).collect());
+            } else if options.is_bare(&key_path[0]) {
+                groups.push(values.into_iter().map(Arg::Value).collect());
+            } else {
+                let values = match &options.array_delimiter {
+                    Some(delimiter) if item.is_array() && !values.is_empty() => {
+                        vec![values.join(delimiter)]
+                    }
+                    _ => values,
+                };
+                let count = if options.is_count(&key_path) {
+                    let count = item.as_u64().ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "{}: a count must be a non-negative integer",
+                            key_path.join(".")
+                        )
+                    })?;
+                    Some(count)
+                } else {
+                    None
+                };
+                let repeated =
+                    item.is_array() && options.array_style(&key_path) == ArrayStyle::Repeat;
+                // `\_key` escapes a key starting with the bare prefix, so that it is printed
+                // as `--_key`.
+                if let Some(key) = key_path[0].strip_prefix('\\') {
+                    key_path[0] = key.to_string();
+                }
+                let mut key = args::rename(key_path, &options.renames);
+                if negated {
+                    key[0] = format!("no-{}", key[0]);
+                }
+                if let Some(count) = count {
+                    let tokens = args::count_tokens(&key, count, options)?;
+                    if !tokens.is_empty() {
+                        groups.push(vec![Arg::Tokens(tokens)]);
+                    }
+                } else if repeated {
+                    let repeats = values.into_iter().map(|value| Arg::Option {
+                        key: key.clone(),
+                        values: vec![value],
+                    });
+                    groups.push(repeats.collect());
+                } else {
+                    groups.push(vec![Arg::Option { key, values }]);
+                }
             }
+        }
 
-            panic!(
-                "Only number, string, array and object are supported as an item of json config file."
-            );
+        match options.args_position {
+            ArgsPosition::Before => groups.insert(0, positionals),
+            ArgsPosition::After => groups.push(positionals),
+        }
+        if !trailing.is_empty() {
+            trailing.insert(0, Arg::Value("--".to_string()));
+            groups.push(trailing);
         }
     } else {
+        let mut args = Vec::new();
         if config.is_array() {
-            let string_array = convert_vec_to_string_vec(config.as_array().unwrap());
-            let joined = string_array.join(" ");
-            args.push_str(&format!("{joined} "));
+            let values = convert_vec_to_string_vec(prefix, config.as_array().unwrap(), options)?;
+            args.extend(values.into_iter().map(Arg::Value));
         }
 
         if config.is_number() {
-            let value = config.as_f64().unwrap();
-            args.push_str(&format!("{value} "));
+            args.push(Arg::Value(number_to_string(config, options)));
         }
 
         if config.is_string() {
             let value = config.as_str().unwrap();
-            args.push_str(&format!("{value} "));
+            args.push(Arg::Value(value.to_string()));
+        }
+        groups.push(args);
+    }
+
+    Ok(groups)
+}
+
+/// Generates the args of a key with `$` directives, like `{"$value": 0.1, "$flag": "-l",
+/// "$style": "equals", "$quote": false}`, which override the flag, the style or the
+/// quoting of the key. `"$count": true` prints the flag as many times as the value, and
+/// `"$type": "duration"` converts the value into a number.
+fn generate_directive_args(
+    key_path: &[String],
+    directives: &Value,
+    options: &ArgOptions,
+) -> anyhow::Result<Vec<Arg>> {
+    let mut options = options.clone();
+    let mut value = Value::Null;
+    let mut flag = None;
+    let mut quote = true;
+    let mut kind = None;
+    let mut is_overridden = false;
+    for (directive, item) in directives.as_object().unwrap() {
+        match (directive.as_str(), item) {
+            (VALUE_DIRECTIVE, item) => value = item.clone(),
+            ("$flag", Value::String(item)) => flag = Some(item.clone()),
+            ("$style", Value::String(item)) => options.style = item.parse()?,
+            ("$quote", Value::Bool(item)) => quote = *item,
+            ("$count", Value::Bool(true)) => options.counts.push(key_path.join(".")),
+            ("$count", Value::Bool(false)) => {}
+            ("$type", Value::String(item)) => kind = Some(item.clone()),
+            _ => anyhow::bail!(
+                "{}: invalid directive `{directive}`: {item}",
+                key_path.join(".")
+            ),
         }
+        is_overridden |= directive != VALUE_DIRECTIVE;
+    }
+
+    if let Some(kind) = &kind {
+        value = convert_typed_value(key_path, value, kind, &options)?;
+    }
+
+    let (prefix, key) = key_path.split_at(key_path.len() - 1);
+    let mut object = serde_json::Map::new();
+    object.insert(key[0].clone(), value);
+    let args = generate_args(&Value::Object(object), prefix, &options)?;
+    if !is_overridden {
+        return Ok(args);
+    }
+
+    // The args are rendered here, since the options used to render the other args
+    // don't apply to them.
+    let args = args.into_iter().map(|arg| {
+        let tokens = match &arg {
+            Arg::Option { key, values } => {
+                args::option_tokens(key, values, flag.as_deref(), &options)
+            }
+            arg => args::tokens(std::slice::from_ref(arg), &options),
+        };
+        if quote {
+            Arg::Tokens(tokens)
+        } else {
+            Arg::Raw(tokens.join(" "))
+        }
+    });
+
+    Ok(args.collect())
+}
+
+/// Generates the args of an array of objects or arrays, either by repeating the group of flags of
+/// every item or by putting the index of every item in its key path.
+fn generate_nested_array_args(
+    key_path: &[String],
+    items: &[Value],
+    options: &ArgOptions,
+) -> anyhow::Result<Vec<Arg>> {
+    let (prefix, key) = key_path.split_at(key_path.len() - 1);
+    let mut args = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        let mut object = serde_json::Map::new();
+        match (options.nested_arrays, item.as_array()) {
+            (NestedArrays::Group, _) => {
+                object.insert(key[0].clone(), item.clone());
+                args.extend(generate_args(&Value::Object(object), prefix, options)?);
+            }
+            // The arrays in an indexed array are indexed as well, even of scalars.
+            (NestedArrays::Indexed, Some(items)) => {
+                let mut item_path = key_path.to_vec();
+                item_path.push(i.to_string());
+                args.extend(generate_nested_array_args(&item_path, items, options)?);
+            }
+            (NestedArrays::Indexed, None) => {
+                object.insert(i.to_string(), item.clone());
+                args.extend(generate_args(&Value::Object(object), key_path, options)?);
+            }
+        }
+    }
+
+    Ok(args)
+}
+
+/// Formats a number. Integers are printed as they are rather than through `f64`, which
+/// cannot hold every 64-bit integer, up to `u64::MAX` for IDs and hashes. The other
+/// numbers are printed in the float format.
+fn number_to_string(number: &Value, options: &ArgOptions) -> String {
+    if let Some(integer) = number.as_i64() {
+        integer.to_string()
+    } else if let Some(integer) = number.as_u64() {
+        integer.to_string()
+    } else {
+        options.float_format.format(number.as_f64().unwrap())
+    }
+}
+
+/// Converts a string holding a duration into a number with `--parse-durations`, and
+/// returns any other string as it is.
+fn string_to_string(text: &str, options: &ArgOptions) -> String {
+    if options.parse_durations {
+        if let Some(duration) = units::parse_duration(text, options.duration_unit) {
+            return number_to_string(&unit_number(duration), options);
+        }
+    }
+
+    text.to_string()
+}
+
+/// Converts a number parsed from a string in a unit into a JSON number, which is an
+/// integer unless it has a fraction.
+fn unit_number(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < 2f64.powi(53) {
+        Value::from(number as i64)
+    } else {
+        Value::from(number)
     }
+}
+
+/// Converts the string value of a key with a `$type` directive, or every string in an
+/// array of them, into a number of the type, like `"duration"` for `"1h30m"` or `"size"`
+/// for `"2GiB"`.
+fn convert_typed_value(
+    key_path: &[String],
+    value: Value,
+    kind: &str,
+    options: &ArgOptions,
+) -> anyhow::Result<Value> {
+    let text = match value {
+        Value::String(text) => text,
+        Value::Array(items) => {
+            let items: Vec<Value> = items
+                .into_iter()
+                .map(|item| convert_typed_value(key_path, item, kind, options))
+                .collect::<anyhow::Result<_>>()?;
+            return Ok(Value::Array(items));
+        }
+        value => return Ok(value),
+    };
 
-    args.trim_end().to_string()
+    let number = match kind {
+        "duration" => units::parse_duration(&text, options.duration_unit),
+        "size" => units::parse_size(&text).map(|bytes| bytes as f64),
+        _ => anyhow::bail!("{}: unsupported type `{kind}`", key_path.join(".")),
+    };
+    let number =
+        number.ok_or_else(|| anyhow::anyhow!("{}: invalid {kind} `{text}`", key_path.join(".")))?;
+    Ok(unit_number(number))
 }
 
-fn convert_vec_to_string_vec(vec: &[Value]) -> Vec<String> {
+fn convert_vec_to_string_vec(
+    key_path: &[String],
+    vec: &[Value],
+    options: &ArgOptions,
+) -> anyhow::Result<Vec<String>> {
     let mut result = Vec::new();
     for item in vec {
         if item.is_number() {
-            result.push(item.as_f64().unwrap().to_string());
+            result.push(number_to_string(item, options));
             continue;
         }
 
         if item.is_string() {
-            result.push(item.as_str().unwrap().to_string());
+            result.push(string_to_string(item.as_str().unwrap(), options));
             continue;
         }
 
-        panic!("Only number and string are supported as an item of Array");
+        if item.is_boolean() {
+            result.push(item.as_bool().unwrap().to_string());
+            continue;
+        }
+
+        anyhow::bail!(
+            "{}: arrays and nulls in an array are only supported with `--nested-arrays indexed`",
+            key_path.join(".")
+        );
     }
 
-    result
+    Ok(result)
 }
 
-fn eval_as_tera_template(template_string: &str) -> anyhow::Result<String> {
-    let context = tera::Context::new();
-    Ok(tera::Tera::one_off(template_string, &context, true)?)
+/// Evaluates the args as a template with the context of the config and the variables.
+fn eval_as_template(
+    templates: &mut Templates,
+    template_string: &str,
+    config: &Value,
+    vars: &[(String, Value)],
+) -> anyhow::Result<String> {
+    templates.render_str(template_string, &template_context(config, vars)?)
+}
+
+/// Evaluates every string value of the config holding template tags as its own
+/// template with the context of the config, so that its output stays a single arg.
+fn eval_template_values(
+    templates: &mut Templates,
+    config: &mut Value,
+    vars: &[(String, Value)],
+) -> anyhow::Result<()> {
+    let context = template_context(config, vars)?;
+    eval_template_value(templates, config, &context)
+}
+
+fn eval_template_value(
+    templates: &mut Templates,
+    value: &mut Value,
+    context: &Value,
+) -> anyhow::Result<()> {
+    match value {
+        Value::String(text) if has_template_tags(text) => {
+            *text = templates.render_str(text, context)?;
+        }
+        Value::Array(items) => {
+            for item in items {
+                eval_template_value(templates, item, context)?;
+            }
+        }
+        Value::Object(object) => {
+            for item in object.values_mut() {
+                eval_template_value(templates, item, context)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Renders the template named in the template directory with the context of the
+/// config and the variables.
+fn render_template(
+    templates: &Templates,
+    name: &str,
+    config: &Value,
+    vars: &[(String, Value)],
+) -> anyhow::Result<String> {
+    templates.render(name, &template_context(config, vars)?)
+}
+
+/// Creates the context of a template, which holds the values of the config, like
+/// `{{ training.lr }}`, and the environment variables as `env`, like `{{ env.HOME }}`,
+/// unless the config has an `env` key. The variables given with `--var` are set at
+/// their dotted key paths over them.
+fn template_context(config: &Value, vars: &[(String, Value)]) -> anyhow::Result<Value> {
+    let mut context = match config {
+        Value::Object(object) => object.clone(),
+        _ => serde_json::Map::new(),
+    };
+    if !context.contains_key(ENV_CONTEXT_KEY) {
+        let variables: serde_json::Map<String, Value> = env_vars()
+            .map(|(name, value)| (name, Value::String(value)))
+            .collect();
+        context.insert(ENV_CONTEXT_KEY.to_string(), Value::Object(variables));
+    }
+
+    let mut context = Value::Object(context);
+    for (path, value) in vars {
+        merge::set_path(&mut context, path, value.clone())?;
+    }
+
+    Ok(context)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use args::{FloatFormat, Style};
+
+    fn tera() -> Templates {
+        Templates::new(Engine::Tera, None).unwrap()
+    }
 
     #[test]
     fn generate_args_string_with_long_keys() {
         let config = json!({"key1": 1, "key2": "udon"});
-        assert_eq!(generate_args_string(&config, None), "--key1 1 --key2 udon");
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "--key1 1 --key2 udon"
+        );
     }
 
     #[test]
     fn generate_args_string_with_short_keys() {
         let config = json!({"a": 1, "b": "udon"});
-        assert_eq!(generate_args_string(&config, None), "-a 1 -b udon");
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "-a 1 -b udon"
+        );
     }
 
     #[test]
     fn generate_args_string_with_array() {
         let config = json!({"key1": 1, "b": "udon", "key3": [1,2,3]});
         assert_eq!(
-            generate_args_string(&config, None),
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
             "--key1 1 -b udon --key3 1 2 3"
         );
     }
 
+    #[test]
+    fn generate_args_string_with_integers() {
+        let config = json!({"epochs": 10, "seed": 9007199254740993i64, "min": -3, "lr": 1.0});
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "--epochs 10 --seed 9007199254740993 --min -3 --lr 1"
+        );
+        let config = json!({"id": u64::MAX, "ids": [18446744073709551614u64, 1]});
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "--id 18446744073709551615 --ids 18446744073709551614 1"
+        );
+    }
+
+    #[test]
+    fn generate_args_string_with_float_format() {
+        let config = json!({"lr": 1e-5, "epochs": 10, "betas": [0.9, 0.999]});
+        let options = ArgOptions {
+            float_format: FloatFormat::Fixed(2),
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            generate_args_string(&config, &options).unwrap(),
+            "--lr 0.00 --epochs 10 --betas 0.90 1.00"
+        );
+        let options = ArgOptions {
+            float_format: FloatFormat::Scientific,
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            generate_args_string(&config, &options).unwrap(),
+            "--lr 1e-5 --epochs 10 --betas 9e-1 9.99e-1"
+        );
+    }
+
     #[test]
     fn generate_args_string_with_string_value() {
         let config = json!("soba");
-        assert_eq!(generate_args_string(&config, None), "soba");
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "soba"
+        );
     }
 
     #[test]
     fn generate_args_string_with_array_value() {
         let config = json!([1, 2, 3]);
-        assert_eq!(generate_args_string(&config, None), "1 2 3");
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "1 2 3"
+        );
     }
 
     #[test]
     fn generate_args_string_without_key() {
         let config = json!({"_skipped_key":1, "not_skipped_key": 2});
-        assert_eq!(generate_args_string(&config, None), "1 --not_skipped_key 2");
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "1 --not_skipped_key 2"
+        );
     }
 
     #[test]
     fn generate_args_string_with_nested_object() {
         let config = json!({"key1":1, "key2": 2, "key3": { "k1": 3, "k2": 4, "k3": { "k4": 5 } }});
         assert_eq!(
-            generate_args_string(&config, None),
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
             "--key1 1 --key2 2 --key3.k1 3 --key3.k2 4 --key3.k3.k4 5"
         );
     }
 
     #[test]
-    #[should_panic]
+    fn generate_args_with_whitespace() {
+        let config = json!({"name": "my model", "layers": [1, 2]});
+        assert_eq!(
+            args::tokens(
+                &generate_args(&config, &[], &ArgOptions::default()).unwrap(),
+                &ArgOptions::default()
+            ),
+            vec!["--name", "my model", "--layers", "1", "2"]
+        );
+    }
+
+    #[test]
+    fn generate_args_with_key_paths() {
+        let config = json!({"model": {"layers": 50}, "_input": "a.txt", "out": true});
+        assert_eq!(
+            generate_args(&config, &[], &ArgOptions::default()).unwrap(),
+            vec![
+                Arg::Option {
+                    key: vec!["model".to_string(), "layers".to_string()],
+                    values: vec!["50".to_string()],
+                },
+                Arg::Value("a.txt".to_string()),
+                Arg::Option {
+                    key: vec!["out".to_string()],
+                    values: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn generate_args_string_with_booleans() {
+        let config = json!({"verbose": true, "dry-run": false, "flags": [true, false]});
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "--verbose --flags true false"
+        );
+        assert_eq!(
+            generate_args_string(
+                &config,
+                &ArgOptions {
+                    explicit_booleans: true,
+                    ..ArgOptions::default()
+                }
+            )
+            .unwrap(),
+            "--verbose true --dry-run false --flags true false"
+        );
+        assert_eq!(
+            generate_args_string(
+                &config,
+                &ArgOptions {
+                    negate_false: true,
+                    ..ArgOptions::default()
+                }
+            )
+            .unwrap(),
+            "--verbose --no-dry-run --flags true false"
+        );
+    }
+
+    #[test]
+    fn generate_args_string_with_null_policies() {
+        let config = json!({"key1": null, "key2": 1, "_input": null});
+        let options = |null_policy| ArgOptions {
+            null_policy,
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            generate_args_string(&config, &options(NullPolicy::SkipPair)).unwrap(),
+            "--key2 1"
+        );
+        assert_eq!(
+            generate_args_string(&config, &options(NullPolicy::FlagOnly)).unwrap(),
+            "--key1 --key2 1"
+        );
+        assert_eq!(
+            generate_args_string(&config, &options(NullPolicy::LiteralNull)).unwrap(),
+            "--key1 null --key2 1 null"
+        );
+        assert!(generate_args_string(&config, &options(NullPolicy::Error)).is_err());
+    }
+
+    #[test]
+    fn generate_args_string_with_renames() {
+        let config = json!({"learning_rate": 0.1, "model": {"layers": 50}, "cache": false});
+        let options = ArgOptions {
+            negate_false: true,
+            renames: HashMap::from([
+                ("learning_rate".to_string(), "lr".to_string()),
+                ("model.layers".to_string(), "depth".to_string()),
+                ("cache".to_string(), "use-cache".to_string()),
+            ]),
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            generate_args_string(&config, &options).unwrap(),
+            "--lr 0.1 --depth 50 --no-use-cache"
+        );
+    }
+
+    #[test]
+    fn generate_args_string_with_positional_args() {
+        let config = json!({"_args": ["in.txt", "out.txt"], "lr": 0.1, "model": {"_args": 1}});
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "--lr 0.1 --model._args 1 in.txt out.txt"
+        );
+        let options = ArgOptions {
+            args_position: ArgsPosition::Before,
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            generate_args_string(&config, &options).unwrap(),
+            "in.txt out.txt --lr 0.1 --model._args 1"
+        );
+    }
+
+    #[test]
+    fn generate_args_string_with_trailing_args() {
+        let config = json!({"_trailing": ["--port", 8080], "_args": "train.py", "lr": 0.1});
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "--lr 0.1 train.py -- --port 8080"
+        );
+        let config = json!({"_trailing": [], "lr": 0.1});
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "--lr 0.1"
+        );
+    }
+
+    #[test]
+    fn generate_args_string_with_array_styles() {
+        let config = json!({"tags": ["a", "b"], "model": {"sizes": [1, 2]}});
+        let mut options = ArgOptions {
+            array_style: ArrayStyle::Repeat,
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            generate_args_string(&config, &options).unwrap(),
+            "--tags a --tags b --model.sizes 1 --model.sizes 2"
+        );
+        options
+            .array_styles
+            .insert("model.sizes".to_string(), ArrayStyle::Space);
+        assert_eq!(
+            generate_args_string(&config, &options).unwrap(),
+            "--tags a --tags b --model.sizes 1 2"
+        );
+    }
+
+    #[test]
+    fn generate_args_string_with_array_delimiter() {
+        let config = json!({"gpus": [0, 1, 2], "_inputs": ["a", "b"], "tags": []});
+        let options = ArgOptions {
+            array_delimiter: Some(",".to_string()),
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            generate_args_string(&config, &options).unwrap(),
+            "--gpus 0,1,2 a b --tags"
+        );
+    }
+
+    #[test]
+    fn generate_args_string_with_arrays_of_objects() {
+        let config = json!({"hosts": [{"name": "a", "port": 1}, {"name": "b"}]});
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "--hosts.name a --hosts.port 1 --hosts.name b"
+        );
+        let options = ArgOptions {
+            nested_arrays: NestedArrays::Indexed,
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            generate_args_string(&config, &options).unwrap(),
+            "--hosts.0.name a --hosts.0.port 1 --hosts.1.name b"
+        );
+    }
+
+    #[test]
+    fn generate_args_string_with_indexed_nested_arrays() {
+        let config = json!({"key3": [1, 2, 3, [4, [5]]]});
+        let options = ArgOptions {
+            nested_arrays: NestedArrays::Indexed,
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            generate_args_string(&config, &options).unwrap(),
+            "--key3.0 1 --key3.1 2 --key3.2 3 --key3.3.0 4 --key3.3.1.0 5"
+        );
+    }
+
+    #[test]
+    fn generate_args_with_directives() {
+        let config = json!({
+            "lr": {"$value": 0.1, "$flag": "-l", "$style": "equals"},
+            "filter": {"$value": "a;b", "$quote": false},
+            "epochs": {"$value": 10},
+        });
+        assert_eq!(
+            generate_args(&config, &[], &ArgOptions::default()).unwrap(),
+            vec![
+                Arg::Tokens(vec!["-l=0.1".to_string()]),
+                Arg::Raw("--filter a;b".to_string()),
+                Arg::Option {
+                    key: vec!["epochs".to_string()],
+                    values: vec!["10".to_string()],
+                },
+            ]
+        );
+        let config = json!({"lr": {"$value": 0.1, "$unknown": 1}});
+        assert!(generate_args(&config, &[], &ArgOptions::default()).is_err());
+    }
+
+    #[test]
+    fn generate_args_string_with_bare_prefix() {
+        let config = json!({"@input": "a.txt", "_private": 1, "\\@literal": 2});
+        let options = ArgOptions {
+            bare_prefix: "@".to_string(),
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            generate_args_string(&config, &options).unwrap(),
+            "a.txt --_private 1 --@literal 2"
+        );
+        let config = json!({"\\_private": 1, "_input": "a.txt"});
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "--_private 1 a.txt"
+        );
+    }
+
+    #[test]
+    fn generate_args_string_with_whitespace() {
+        let config = json!({"name": "my model", "owner": "it's", "tag": "{{ 1 }} {{ 2 }}"});
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            r"--name 'my model' --owner 'it'\''s' --tag {{ 1 }} {{ 2 }}"
+        );
+    }
+
+    #[test]
+    fn generate_args_with_raw_fragments() {
+        let config = json!({"_raw": "--weird-flag=x;y z", "lr": 0.1});
+        assert_eq!(
+            generate_args(&config, &[], &ArgOptions::default()).unwrap(),
+            vec![
+                Arg::Raw("--weird-flag=x;y z".to_string()),
+                Arg::Option {
+                    key: vec!["lr".to_string()],
+                    values: vec!["0.1".to_string()],
+                },
+            ]
+        );
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "--weird-flag=x;y z --lr 0.1"
+        );
+    }
+
+    #[test]
+    fn generate_args_string_with_counts() {
+        let config = json!({"v": 3, "debug": 2, "q": 0, "n": {"$value": 2, "$count": true}});
+        let options = ArgOptions {
+            counts: vec!["v".to_string(), "debug".to_string(), "q".to_string()],
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            generate_args_string(&config, &options).unwrap(),
+            "-vvv --debug --debug -nn"
+        );
+        let config = json!({"v": -1});
+        assert!(generate_args_string(&config, &options).is_err());
+    }
+
+    #[test]
+    fn generate_args_string_with_dedup() {
+        let config = json!({"learning_rate": 0.1, "lr": 0.2, "epochs": 10});
+        let options = ArgOptions {
+            renames: HashMap::from([("learning_rate".to_string(), "lr".to_string())]),
+            dedup: args::Dedup::Last,
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            generate_args_string(&config, &options).unwrap(),
+            "--lr 0.2 --epochs 10"
+        );
+    }
+
+    #[test]
+    fn generate_args_string_with_durations() {
+        let config = json!({
+            "timeout": "1h30m",
+            "delays": ["500ms", "2s"],
+            "name": "5m",
+            "interval": {"$value": "1.5s", "$type": "duration"}
+        });
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "--timeout 1h30m --delays 500ms 2s --name 5m --interval 1.5"
+        );
+        let options = ArgOptions {
+            parse_durations: true,
+            duration_unit: units::DurationUnit::Milliseconds,
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            generate_args_string(&config, &options).unwrap(),
+            "--timeout 5400000 --delays 500 2000 --name 300000 --interval 1500"
+        );
+
+        let config = json!({"timeout": {"$value": "soon", "$type": "duration"}});
+        assert!(generate_args_string(&config, &ArgOptions::default()).is_err());
+    }
+
+    #[test]
+    fn generate_args_string_with_sizes() {
+        let config = json!({
+            "memory": "2GiB",
+            "buffers": ["512k", "1M"],
+            "name": "1k",
+            "cache": {"$value": "64Mi", "$type": "size"}
+        });
+        let options = ArgOptions {
+            sizes: vec!["memory".to_string(), "buffers".to_string()],
+            ..ArgOptions::default()
+        };
+        assert_eq!(
+            generate_args_string(&config, &options).unwrap(),
+            "--memory 2147483648 --buffers 512000 1000000 --name 1k --cache 67108864"
+        );
+        let config = json!({"memory": "lots"});
+        assert!(generate_args_string(&config, &options).is_err());
+    }
+
+    #[test]
+    fn generate_args_string_with_equals_style() {
+        let config = json!({"key1": 1, "b": "udon", "key3": [1, 2]});
+        assert_eq!(
+            generate_args_string(
+                &config,
+                &ArgOptions {
+                    style: Style::Equals,
+                    ..ArgOptions::default()
+                }
+            )
+            .unwrap(),
+            "--key1=1 -b=udon --key3=1 2"
+        );
+    }
+
+    #[test]
     fn generate_args_string_with_nested_array() {
         let config = json!({"key1": 1, "b": "udon", "key3": [1,2,3, [4]]});
-        generate_args_string(&config, None);
+        assert!(generate_args_string(&config, &ArgOptions::default()).is_err());
+        let config = json!({"key3": [1, null]});
+        assert!(generate_args_string(&config, &ArgOptions::default()).is_err());
+    }
+
+    #[cfg(feature = "jmespath")]
+    #[test]
+    fn generate_args_string_of_a_jmespath_selection() {
+        let config = json!({"jobs": [{"params": {"b": 1, "a": 2}}]});
+        let config = select::jmespath(&config, "jobs[0].params").unwrap();
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "--b 1 --a 2"
+        );
+    }
+
+    #[test]
+    fn load_config_file_with_extends() {
+        let dir = env::temp_dir().join("config2args-extends");
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::write(
+            dir.join("base/defaults.yml"),
+            "lr: 0.1\nmodel:\n  arch: resnet50\n  layers: 50\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("config.json"),
+            r#"{"_extends": "base/defaults.yml", "model": {"layers": 101}, "seed": 1}"#,
+        )
+        .unwrap();
+
+        let documents = load_config_file(
+            dir.join("config.json").to_str().unwrap(),
+            None,
+            &MergeOptions::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            documents,
+            vec![json!({"lr": 0.1, "model": {"arch": "resnet50", "layers": 101}, "seed": 1})]
+        );
+    }
+
+    #[test]
+    fn load_config_file_with_circular_extends() {
+        let dir = env::temp_dir().join("config2args-circular-extends");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.json"), r#"{"_extends": "b.json"}"#).unwrap();
+        std::fs::write(dir.join("b.json"), r#"{"_extends": "a.json"}"#).unwrap();
+
+        assert!(
+            load_config_file(
+                dir.join("a.json").to_str().unwrap(),
+                None,
+                &MergeOptions::default(),
+                None
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parent_sources() {
+        assert_eq!(
+            parent_source("configs/train.json", "base.json"),
+            "configs/base.json"
+        );
+        assert_eq!(
+            parent_source("configs/train.json", "https://example.com/base.json"),
+            "https://example.com/base.json"
+        );
     }
 
     #[test]
     fn eval_as_a_tera_template() {
         let config = json!({"key1": "{% set my_var = [1, 2, 3, 4] %}{% for i in my_var %}{{i}} {% endfor %}"});
-        let raw_string = generate_args_string(&config, None);
+        let raw_string = generate_args_string(&config, &ArgOptions::default()).unwrap();
         assert_eq!(
-            eval_as_tera_template(&raw_string).unwrap(),
+            eval_as_template(&mut tera(), &raw_string, &config, &[]).unwrap(),
             "--key1 1 2 3 4 "
         );
     }
 
+    #[test]
+    fn eval_as_a_tera_template_with_config_values() {
+        let config = json!({
+            "training": {"lr": 0.01},
+            "seed": 42,
+            "scaled_lr": "{{ training.lr * 10 }}",
+            "out": "logs/{{ seed }}"
+        });
+        let raw_string = generate_args_string(&config, &ArgOptions::default()).unwrap();
+        assert_eq!(
+            eval_as_template(&mut tera(), &raw_string, &config, &[]).unwrap(),
+            "--training.lr 0.01 --seed 42 --scaled_lr 0.1 --out logs/42"
+        );
+    }
+
+    #[test]
+    fn eval_as_a_tera_template_with_env() {
+        let path = env::var("PATH").unwrap();
+        let config = json!({"path": "{{ env.PATH }}", "home": "{{ get_env(name=\"C2A_UNSET\", default=\"none\") }}"});
+        let raw_string = generate_args_string(&config, &ArgOptions::default()).unwrap();
+        assert_eq!(
+            eval_as_template(&mut tera(), &raw_string, &config, &[]).unwrap(),
+            format!("--path {path} --home none")
+        );
+
+        let config = json!({"env": {"MODE": "dev"}, "mode": "{{ env.MODE }}"});
+        let raw_string = generate_args_string(&config, &ArgOptions::default()).unwrap();
+        assert_eq!(
+            eval_as_template(&mut tera(), &raw_string, &config, &[]).unwrap(),
+            "--env.MODE dev --mode dev"
+        );
+    }
+
+    #[test]
+    fn eval_a_template_file() {
+        let dir = env::temp_dir().join("config2args-template");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("launch.tera");
+        std::fs::write(&path, "train.py --lr {{ lr }} {{ data | shell_quote }}\n").unwrap();
+
+        let template = load_template(path.to_str().unwrap()).unwrap();
+        let config = json!({"lr": 0.1, "data": "my data"});
+        assert_eq!(
+            eval_as_template(&mut tera(), &template, &config, &[]).unwrap(),
+            "train.py --lr 0.1 'my data'\n"
+        );
+    }
+
+    #[test]
+    fn render_a_template_in_a_template_dir() {
+        let dir = env::temp_dir().join("config2args-template-dir");
+        std::fs::create_dir_all(dir.join("common")).unwrap();
+        std::fs::write(
+            dir.join("common/base.tera"),
+            "python {% block script %}{% endblock %} {% include \"common/data.tera\" %}",
+        )
+        .unwrap();
+        std::fs::write(dir.join("common/data.tera"), "--data {{ data }}").unwrap();
+        std::fs::write(
+            dir.join("train.tera"),
+            "{% extends \"common/base.tera\" %}{% block script %}train.py --lr {{ lr }}{% endblock %}",
+        )
+        .unwrap();
+
+        let templates = Templates::new(Engine::Tera, dir.to_str()).unwrap();
+        let config = json!({"lr": 0.1, "data": "/data"});
+        assert_eq!(
+            render_template(&templates, "train.tera", &config, &[]).unwrap(),
+            "python train.py --lr 0.1 --data /data"
+        );
+        assert!(render_template(&templates, "eval.tera", &config, &[]).is_err());
+    }
+
+    #[cfg(feature = "minijinja")]
+    #[test]
+    fn eval_a_jinja2_config_without_engine() {
+        let dir = env::temp_dir().join("config2args-jinja2");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json.j2");
+        std::fs::write(
+            &path,
+            r#"{"fast": true, "out": "logs/{{ 'fast' if fast else 'slow' }}"}"#,
+        )
+        .unwrap();
+
+        let options = cli::parse_args(&[path.to_str().unwrap().to_string()]).unwrap();
+        let engine = template_engine(&options);
+        assert_eq!(engine, Engine::MiniJinja);
+        let config = load_config_file(
+            &options.config_paths[0],
+            None,
+            &MergeOptions::default(),
+            None,
+        )
+        .unwrap()
+        .remove(0);
+        let raw_string = generate_args_string(&config, &ArgOptions::default()).unwrap();
+        assert_eq!(
+            eval_as_template(
+                &mut Templates::new(engine, None).unwrap(),
+                &raw_string,
+                &config,
+                &[]
+            )
+            .unwrap(),
+            "--fast --out logs/fast"
+        );
+    }
+
+    #[test]
+    fn eval_as_a_template_with_vars() {
+        let config = json!({"out": "runs/{{ run.id }}/gpu{{ gpu }}", "gpu": 0});
+        let vars = vec![
+            ("run.id".to_string(), json!("a1")),
+            ("gpu".to_string(), json!(3)),
+        ];
+        let raw_string = generate_args_string(&config, &ArgOptions::default()).unwrap();
+        assert_eq!(
+            eval_as_template(&mut tera(), &raw_string, &config, &vars).unwrap(),
+            "--out runs/a1/gpu3 --gpu 0"
+        );
+    }
+
+    #[test]
+    fn eval_template_values_one_by_one() {
+        let mut config = json!({
+            "model": "resnet",
+            "name": "{{ model }} v2",
+            "tags": ["{{ model | upper }}", "--fast"]
+        });
+        eval_template_values(&mut tera(), &mut config, &[]).unwrap();
+        assert_eq!(
+            generate_args_string(&config, &ArgOptions::default()).unwrap(),
+            "--model resnet --name 'resnet v2' --tags RESNET --fast"
+        );
+    }
+
     #[test]
     #[should_panic]
     fn eval_as_an_invalid_tera_template() {
         let config = json!({"key1": "{% set my_var = [1, 2, 3, 4] %}{% for i in my_var %}{{i}} {% endfor %"});
-        let raw_string = generate_args_string(&config, None);
+        let raw_string = generate_args_string(&config, &ArgOptions::default()).unwrap();
         assert_eq!(
-            eval_as_tera_template(&raw_string).unwrap(),
+            eval_as_template(&mut tera(), &raw_string, &config, &[]).unwrap(),
             "--key1 1 2 3 4 "
         );
     }
@@ -0,0 +1,449 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Reserved key holding named profiles, one of which is selected with `--profile`.
+pub const PROFILES_KEY: &str = "_profiles";
+
+/// Reserved top-level key holding blocks which are only used through `$ref`.
+pub const DEFINITIONS_KEY: &str = "$defs";
+
+const REF_KEY: &str = "$ref";
+
+/// How two arrays are merged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrayStrategy {
+    /// The array in the overlay replaces the base array.
+    #[default]
+    Replace,
+    /// The items in the overlay are appended to the base array.
+    Concat,
+    /// The items in the overlay which are not in the base array are appended to it.
+    Union,
+}
+
+impl FromStr for ArrayStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<ArrayStrategy> {
+        match name {
+            "replace" => Ok(ArrayStrategy::Replace),
+            "concat" => Ok(ArrayStrategy::Concat),
+            "union" => Ok(ArrayStrategy::Union),
+            _ => anyhow::bail!("unsupported array merge strategy: {name}"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MergeOptions {
+    pub arrays: ArrayStrategy,
+    /// Strategies for the arrays at specific dotted key paths, like `train.include`.
+    pub array_overrides: HashMap<String, ArrayStrategy>,
+}
+
+impl MergeOptions {
+    fn array_strategy(&self, path: &str) -> ArrayStrategy {
+        self.array_overrides
+            .get(path)
+            .copied()
+            .unwrap_or(self.arrays)
+    }
+}
+
+/// Merges `overlay` into `base`. Objects are merged key by key recursively, arrays
+/// are merged with the strategy in `options`, and any other value in `overlay`
+/// replaces the one in `base`.
+pub fn deep_merge(base: &mut Value, overlay: Value, options: &MergeOptions) {
+    merge_at(base, overlay, options, "");
+}
+
+fn merge_at(base: &mut Value, overlay: Value, options: &MergeOptions, path: &str) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                let key_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                match base.get_mut(&key) {
+                    Some(existing) => merge_at(existing, value, options, &key_path),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(base), Value::Array(overlay)) => match options.array_strategy(path) {
+            ArrayStrategy::Replace => *base = overlay,
+            ArrayStrategy::Concat => base.extend(overlay),
+            ArrayStrategy::Union => {
+                for item in overlay {
+                    if !base.contains(&item) {
+                        base.push(item);
+                    }
+                }
+            }
+        },
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Fills in the keys which are missing or null in the config with the values in
+/// `defaults`. Unlike `deep_merge`, the values in the config are never replaced, and the
+/// objects in both of them are filled in recursively.
+pub fn fill_defaults(config: &mut Value, defaults: &Value) {
+    let (Value::Object(config), Value::Object(defaults)) = (config, defaults) else {
+        return;
+    };
+    for (key, default) in defaults {
+        match config.get_mut(key) {
+            Some(value) if !value.is_null() => fill_defaults(value, default),
+            Some(value) => *value = default.clone(),
+            None => {
+                config.insert(key.clone(), default.clone());
+            }
+        }
+    }
+}
+
+/// Removes the profiles from the config, and deep-merges the selected profile over
+/// the top-level keys.
+pub fn apply_profile(
+    config: &mut Value,
+    profile: Option<&str>,
+    options: &MergeOptions,
+) -> anyhow::Result<()> {
+    let profiles = config
+        .as_object_mut()
+        .and_then(|object| object.shift_remove(PROFILES_KEY));
+
+    let Some(name) = profile else {
+        return Ok(());
+    };
+    let selected = profiles
+        .as_ref()
+        .and_then(|profiles| profiles.get(name))
+        .ok_or_else(|| anyhow::anyhow!("profile `{name}` is not defined"))?;
+    deep_merge(config, selected.clone(), options);
+
+    Ok(())
+}
+
+/// Sets the value at a dotted key path, like `training.lr`, creating the missing
+/// objects on the way. A numeric segment indexes into an existing array.
+pub fn set_path(config: &mut Value, path: &str, value: Value) -> anyhow::Result<()> {
+    let mut current = config;
+    let mut segments = path.split('.').peekable();
+    while let Some(segment) = segments.next() {
+        let is_last = segments.peek().is_none();
+        current = match current {
+            Value::Array(items) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index))
+                .ok_or_else(|| {
+                    anyhow::anyhow!("{path}: `{segment}` is not an index of the array")
+                })?,
+            Value::Object(object) => object.entry(segment).or_insert_with(|| {
+                if is_last {
+                    Value::Null
+                } else {
+                    Value::Object(Default::default())
+                }
+            }),
+            _ => anyhow::bail!("{path}: cannot set `{segment}` in a non-object value"),
+        };
+    }
+    *current = value;
+
+    Ok(())
+}
+
+/// Replaces every `{"$ref": "#/json/pointer"}` object with the value it points to in
+/// the config. Other keys next to `$ref` are deep-merged over the referenced value.
+/// The `$defs` key is removed afterwards, so that the blocks kept in it for `$ref`
+/// do not become args.
+pub fn resolve_refs(config: &mut Value) -> anyhow::Result<()> {
+    let root = config.clone();
+    resolve_refs_in(config, &root, &mut Vec::new())?;
+
+    if let Some(object) = config.as_object_mut() {
+        object.shift_remove(DEFINITIONS_KEY);
+    }
+
+    Ok(())
+}
+
+fn resolve_refs_in(value: &mut Value, root: &Value, stack: &mut Vec<String>) -> anyhow::Result<()> {
+    match value {
+        Value::Object(object) => {
+            for (_, item) in object.iter_mut() {
+                resolve_refs_in(item, root, stack)?;
+            }
+
+            let Some(reference) = object.shift_remove(REF_KEY) else {
+                return Ok(());
+            };
+            let reference = reference
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("{REF_KEY} must be a string: {reference}"))?;
+            let pointer = reference.strip_prefix('#').ok_or_else(|| {
+                anyhow::anyhow!("{reference}: only references within the config are supported")
+            })?;
+            if stack.iter().any(|item| item == pointer) {
+                anyhow::bail!("circular {REF_KEY}: {}", reference);
+            }
+
+            let mut target = root
+                .pointer(pointer)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("{reference}: {REF_KEY} target is not found"))?;
+            stack.push(pointer.to_string());
+            resolve_refs_in(&mut target, root, stack)?;
+            stack.pop();
+
+            let siblings = std::mem::take(object);
+            if !siblings.is_empty() {
+                deep_merge(
+                    &mut target,
+                    Value::Object(siblings),
+                    &MergeOptions::default(),
+                );
+            }
+            *value = target;
+        }
+        Value::Array(items) => {
+            for item in items {
+                resolve_refs_in(item, root, stack)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Collects the overrides from environment variables named `PREFIX__KEY__PATH`. The
+/// segments after the prefix are lowercased and joined with `.`, so `C2A__TRAINING__LR`
+/// overrides `training.lr`, and are matched to the keys of a config with
+/// `match_key_case`. Variables are sorted by name to apply them in a stable order.
+pub fn env_overrides(
+    prefix: &str,
+    vars: impl Iterator<Item = (String, String)>,
+) -> Vec<(String, Value)> {
+    let prefix = format!("{prefix}__");
+    let mut overrides: Vec<(String, Value)> = vars
+        .filter_map(|(name, value)| {
+            let path = name.strip_prefix(&prefix)?;
+            let path = path
+                .split("__")
+                .collect::<Vec<_>>()
+                .join(".")
+                .to_lowercase();
+            Some((path, crate::cli::parse_value(&value)))
+        })
+        .collect();
+    overrides.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    overrides
+}
+
+/// Resolves every segment of a lowercase key path, like `training.learningrate`, to the
+/// existing key which equals it ignoring case, like `training.learningRate`. Segments
+/// without such a key are kept lowercase.
+pub fn match_key_case(config: &Value, path: &str) -> String {
+    let mut current = Some(config);
+    let mut segments = Vec::new();
+    for segment in path.split('.') {
+        let key = current
+            .and_then(Value::as_object)
+            .and_then(|object| object.keys().find(|key| key.to_lowercase() == segment))
+            .cloned()
+            .unwrap_or_else(|| segment.to_string());
+        current = current.and_then(|value| match segment.parse::<usize>() {
+            Ok(index) if value.is_array() => value.get(index),
+            _ => value.get(key.as_str()),
+        });
+        segments.push(key);
+    }
+
+    segments.join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn deep_merge_objects() {
+        let mut base = json!({"key1": 1, "key2": {"k1": 2, "k2": 3}, "key3": [1, 2]});
+        deep_merge(
+            &mut base,
+            json!({"key2": {"k2": 4, "k3": 5}, "key3": [3], "key4": "udon"}),
+            &MergeOptions::default(),
+        );
+        assert_eq!(
+            base,
+            json!({"key1": 1, "key2": {"k1": 2, "k2": 4, "k3": 5}, "key3": [3], "key4": "udon"})
+        );
+    }
+
+    #[test]
+    fn deep_merge_replaces_non_objects() {
+        let mut base = json!({"key1": {"k1": 1}});
+        deep_merge(&mut base, json!({"key1": "udon"}), &MergeOptions::default());
+        assert_eq!(base, json!({"key1": "udon"}));
+    }
+
+    #[test]
+    fn deep_merge_arrays_with_strategies() {
+        let options = MergeOptions {
+            arrays: ArrayStrategy::Concat,
+            array_overrides: HashMap::from([("train.include".to_string(), ArrayStrategy::Union)]),
+        };
+        let mut base = json!({"layers": [1, 2], "train": {"include": ["a", "b"]}});
+        deep_merge(
+            &mut base,
+            json!({"layers": [2, 3], "train": {"include": ["b", "c"]}}),
+            &options,
+        );
+        assert_eq!(
+            base,
+            json!({"layers": [1, 2, 2, 3], "train": {"include": ["a", "b", "c"]}})
+        );
+    }
+
+    #[test]
+    fn apply_selected_profile() {
+        let mut config = json!({
+            "lr": 0.1,
+            "out": "logs",
+            "_profiles": {"dev": {"out": "logs/dev"}, "prod": {"lr": 0.01}}
+        });
+        apply_profile(&mut config, Some("prod"), &MergeOptions::default()).unwrap();
+        assert_eq!(config, json!({"lr": 0.01, "out": "logs"}));
+    }
+
+    #[test]
+    fn fill_in_defaults() {
+        let mut config = json!({"lr": 0.1, "seed": null, "model": {"layers": 50}, "tags": [1]});
+        fill_defaults(
+            &mut config,
+            &json!({
+                "lr": 1,
+                "seed": 42,
+                "epochs": 10,
+                "model": {"layers": 18, "width": 64},
+                "tags": [2, 3]
+            }),
+        );
+        assert_eq!(
+            config,
+            json!({
+                "lr": 0.1,
+                "seed": 42,
+                "model": {"layers": 50, "width": 64},
+                "tags": [1],
+                "epochs": 10
+            })
+        );
+    }
+
+    #[test]
+    fn apply_no_profile() {
+        let mut config = json!({"lr": 0.1, "_profiles": {"dev": {"lr": 1}}});
+        apply_profile(&mut config, None, &MergeOptions::default()).unwrap();
+        assert_eq!(config, json!({"lr": 0.1}));
+        assert!(apply_profile(&mut config, Some("dev"), &MergeOptions::default()).is_err());
+    }
+
+    #[test]
+    fn set_path_values() {
+        let mut config = json!({"lr": 0.1, "model": {"layers": [1, 2]}});
+        set_path(&mut config, "lr", json!(0.01)).unwrap();
+        set_path(&mut config, "model.layers.1", json!(3)).unwrap();
+        set_path(&mut config, "data.path", json!("/data")).unwrap();
+        assert_eq!(
+            config,
+            json!({"lr": 0.01, "model": {"layers": [1, 3]}, "data": {"path": "/data"}})
+        );
+        assert!(set_path(&mut config, "lr.value", json!(1)).is_err());
+    }
+
+    #[test]
+    fn collect_env_overrides() {
+        let vars = vec![
+            ("C2A__TRAINING__LR".to_string(), "0.01".to_string()),
+            ("C2A__OUT".to_string(), "logs/ci".to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+        ];
+        assert_eq!(
+            env_overrides("C2A", vars.into_iter()),
+            vec![
+                ("out".to_string(), json!("logs/ci")),
+                ("training.lr".to_string(), json!(0.01)),
+            ]
+        );
+    }
+
+    #[test]
+    fn match_key_case_of_env_overrides() {
+        let config = json!({"training": {"learningRate": 0.1, "layers": [{"numHeads": 8}]}});
+        assert_eq!(
+            match_key_case(&config, "training.learningrate"),
+            "training.learningRate"
+        );
+        assert_eq!(
+            match_key_case(&config, "training.layers.0.numheads"),
+            "training.layers.0.numHeads"
+        );
+        assert_eq!(
+            match_key_case(&config, "training.warmup.steps"),
+            "training.warmup.steps"
+        );
+    }
+
+    #[test]
+    fn resolve_json_pointer_refs() {
+        let mut config = json!({
+            "$defs": {"adam": {"name": "adam", "lr": 0.001}},
+            "encoder": {"optimizer": {"$ref": "#/$defs/adam"}},
+            "decoder": {"optimizer": {"$ref": "#/$defs/adam", "lr": 0.01}},
+            "seed": {"$ref": "#/encoder/optimizer/lr"}
+        });
+        resolve_refs(&mut config).unwrap();
+        assert_eq!(
+            config,
+            json!({
+                "encoder": {"optimizer": {"name": "adam", "lr": 0.001}},
+                "decoder": {"optimizer": {"name": "adam", "lr": 0.01}},
+                "seed": 0.001
+            })
+        );
+    }
+
+    #[test]
+    fn resolve_refs_outside_definitions() {
+        let mut config = json!({
+            "defaults": {"optimizer": {"name": "adam"}},
+            "training": {"optimizer": {"$ref": "#/defaults/optimizer", "lr": 0.1}}
+        });
+        resolve_refs(&mut config).unwrap();
+        assert_eq!(
+            config,
+            json!({
+                "defaults": {"optimizer": {"name": "adam"}},
+                "training": {"optimizer": {"name": "adam", "lr": 0.1}}
+            })
+        );
+    }
+
+    #[test]
+    fn resolve_circular_refs() {
+        let mut config = json!({"a": {"$ref": "#/b"}, "b": {"$ref": "#/a"}});
+        assert!(resolve_refs(&mut config).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use serde_json::{Map, Value};
+use std::str::FromStr;
+
+/// Reserved key holding the keys of an object in the order they are printed.
+pub const ORDER_KEY: &str = "_order";
+
+/// The order in which the keys of the objects are printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sort {
+    /// The order of the config.
+    Input,
+    /// The alphabetical order of the keys.
+    Alpha,
+    /// The order of the `_order` array of every object, followed by the other keys in
+    /// the order of the config.
+    #[default]
+    Spec,
+}
+
+impl FromStr for Sort {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<Sort> {
+        match name {
+            "input" => Ok(Sort::Input),
+            "alpha" => Ok(Sort::Alpha),
+            "spec" => Ok(Sort::Spec),
+            _ => anyhow::bail!("unsupported sort: {name}"),
+        }
+    }
+}
+
+/// Reorders the keys of every object in the config, and removes the `_order` arrays.
+pub fn apply(config: &mut Value, sort: Sort) -> anyhow::Result<()> {
+    let object = match config {
+        Value::Object(object) => object,
+        Value::Array(items) => {
+            for item in items {
+                apply(item, sort)?;
+            }
+            return Ok(());
+        }
+        _ => return Ok(()),
+    };
+
+    let order = object.shift_remove(ORDER_KEY);
+    for value in object.values_mut() {
+        apply(value, sort)?;
+    }
+
+    let mut keys: Vec<String> = object.keys().cloned().collect();
+    match (sort, order) {
+        (Sort::Alpha, _) => keys.sort(),
+        (Sort::Spec, Some(order)) => {
+            let order = order_names(&order)?;
+            // Keys which are not in `_order` keep their order after the listed ones.
+            keys.sort_by_key(|key| {
+                order
+                    .iter()
+                    .position(|name| name == key)
+                    .unwrap_or(order.len())
+            });
+        }
+        (Sort::Input, _) | (Sort::Spec, None) => return Ok(()),
+    }
+
+    let mut sorted = Map::new();
+    for key in keys {
+        let value = object.shift_remove(&key).expect("key is in the object");
+        sorted.insert(key, value);
+    }
+    *object = sorted;
+
+    Ok(())
+}
+
+fn order_names(order: &Value) -> anyhow::Result<Vec<&str>> {
+    let names = order
+        .as_array()
+        .and_then(|names| names.iter().map(Value::as_str).collect());
+    names.ok_or_else(|| anyhow::anyhow!("`{ORDER_KEY}` must be an array of keys"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn keys(config: &Value) -> Vec<&str> {
+        config
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect()
+    }
+
+    #[test]
+    fn sort_by_order_key() {
+        let mut config = json!({
+            "b": 1,
+            "_input": "a.txt",
+            "a": {"y": 1, "x": 2, "_order": ["x"]},
+            "_order": ["_input", "a"],
+        });
+        apply(&mut config, Sort::Spec).unwrap();
+        assert_eq!(keys(&config), vec!["_input", "a", "b"]);
+        assert_eq!(keys(&config["a"]), vec!["x", "y"]);
+    }
+
+    #[test]
+    fn sort_alphabetically() {
+        let mut config = json!({"b": 1, "a": {"y": 1, "x": 2}, "_order": ["b"]});
+        apply(&mut config, Sort::Alpha).unwrap();
+        assert_eq!(keys(&config), vec!["a", "b"]);
+        assert_eq!(keys(&config["a"]), vec!["x", "y"]);
+    }
+
+    #[test]
+    fn keep_input_order() {
+        let mut config = json!({"b": 1, "a": 2, "_order": ["a"]});
+        apply(&mut config, Sort::Input).unwrap();
+        assert_eq!(keys(&config), vec!["b", "a"]);
+        assert!(apply(&mut json!({"_order": "a"}), Sort::Spec).is_err());
+    }
+}
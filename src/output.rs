@@ -0,0 +1,711 @@
+use crate::args::{self, Arg, ArgOptions};
+use crate::shell;
+use serde_json::Value;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Reserved key holding the program which the args are passed to, for the outputs
+/// which run it.
+pub const PROGRAM_KEY: &str = "_program";
+
+/// How the generated args are printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Output {
+    /// One line of args for each config, quoted for the shell.
+    #[default]
+    Shell,
+    /// Every arg terminated by NUL, for `xargs -0`.
+    Print0,
+    /// Every arg on its own line, for `readarray` and `diff`.
+    Lines,
+    /// A JSON array of the args for each config.
+    Json,
+    /// A PowerShell hashtable of the options for each config, for splatting.
+    PowerShell,
+    /// `export KEY=VALUE` lines for the options.
+    EnvExport,
+    /// `KEY=VALUE` lines for the options, for `docker --env-file`.
+    EnvFile,
+    /// An `args: [...]` YAML fragment for the container spec of a Kubernetes Pod.
+    K8sArgs,
+    /// An `ExecStart=` line for a systemd unit, running the program with the args.
+    Systemd,
+    /// A `with:` YAML mapping of the options, for the inputs of a GitHub Actions step.
+    GithubWith,
+    /// An `ARGS := ...` Makefile variable.
+    Makefile,
+    /// A Rust `&[&str]` literal of the args.
+    Rust,
+    /// A C `char *[]` compound literal of the args, terminated by `NULL`.
+    C,
+}
+
+impl FromStr for Output {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<Output> {
+        match name {
+            "shell" => Ok(Output::Shell),
+            "print0" => Ok(Output::Print0),
+            "lines" => Ok(Output::Lines),
+            "json" => Ok(Output::Json),
+            "powershell" => Ok(Output::PowerShell),
+            "env-export" => Ok(Output::EnvExport),
+            "env-file" => Ok(Output::EnvFile),
+            "k8s-args" => Ok(Output::K8sArgs),
+            "systemd" => Ok(Output::Systemd),
+            "github-with" => Ok(Output::GithubWith),
+            "makefile" => Ok(Output::Makefile),
+            "rust" => Ok(Output::Rust),
+            "c" => Ok(Output::C),
+            _ => anyhow::bail!("unsupported output format: {name}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct OutputOptions {
+    pub format: Output,
+    /// Prints the values without quoting them for the shell.
+    pub no_quote: bool,
+    /// Program which the args are passed to, given with `--program`.
+    pub program: Option<String>,
+    /// Prints every arg of the `Shell` output on its own line, continued with `\`.
+    pub pretty: bool,
+}
+
+/// Renders the command of a launcher script, which runs the program with the args of
+/// one config, one arg per line, followed by the args given to the script.
+pub fn script_command(
+    args: &[Arg],
+    arg_options: &ArgOptions,
+    options: &OutputOptions,
+) -> anyhow::Result<String> {
+    let program = options
+        .program
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("--emit-script requires --program or `{PROGRAM_KEY}`"))?;
+
+    let mut lines = vec![shell::quote(program).to_string()];
+    for words in shell_words(args, arg_options, false) {
+        lines.push(format!("  {words}"));
+    }
+    lines.push("  \"$@\"".to_string());
+
+    Ok(format!("{}\n", lines.join(" \\\n")))
+}
+
+/// Writes an executable launcher script running the commands in order.
+pub fn write_script(path: &str, commands: &[String]) -> anyhow::Result<()> {
+    let script = format!("#!/bin/sh\nset -e\n\n{}", commands.join("\n"));
+    write_atomically(path, &script, true)
+}
+
+/// Writes the contents to a temporary file next to the path and renames it to the
+/// path, so that readers never see a partially written file.
+pub fn write_atomically(path: &str, contents: &str, executable: bool) -> anyhow::Result<()> {
+    let path = Path::new(path);
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("{}: not a file path", path.display()))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = write_file(&temp_path, contents, executable)
+        .and_then(|()| std::fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result.map_err(|error| anyhow::anyhow!("{}: {error}", path.display()))
+}
+
+fn write_file(path: &Path, contents: &str, executable: bool) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+
+    #[cfg(unix)]
+    if executable {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(not(unix))]
+    let _ = executable;
+
+    Ok(())
+}
+
+/// Removes the `_program` key from the config, and returns the program given with
+/// `--program` or else the one in the key.
+pub fn take_program(config: &mut Value, program: Option<&str>) -> anyhow::Result<Option<String>> {
+    let in_config = config
+        .as_object_mut()
+        .and_then(|object| object.shift_remove(PROGRAM_KEY));
+    if let Some(program) = program {
+        return Ok(Some(program.to_string()));
+    }
+
+    match in_config {
+        Some(Value::String(program)) => Ok(Some(program)),
+        Some(value) => anyhow::bail!("`{PROGRAM_KEY}` must be a string: {value}"),
+        None => Ok(None),
+    }
+}
+
+/// Renders the args generated from one config.
+pub fn render(
+    args: &[Arg],
+    arg_options: &ArgOptions,
+    options: &OutputOptions,
+) -> anyhow::Result<String> {
+    let tokens = || args::tokens(args, arg_options);
+    let words = || shell_words(args, arg_options, options.no_quote);
+    let rendered = match options.format {
+        Output::Shell if options.pretty => {
+            let lines: Vec<String> = words().iter().map(|words| format!("  {words}")).collect();
+            format!("{}\n", lines.join(" \\\n"))
+        }
+        Output::Shell => format!("{}\n", words().join(" ")),
+        Output::Print0 => tokens().iter().map(|token| format!("{token}\0")).collect(),
+        Output::Lines => tokens().iter().map(|token| format!("{token}\n")).collect(),
+        Output::Json => format!("{}\n", Value::from(tokens())),
+        Output::K8sArgs => k8s_args(&tokens()),
+        Output::Systemd => exec_start(&tokens(), options.program.as_deref())?,
+        Output::Makefile => make_variable(&shell_words(args, arg_options, false).join(" ")),
+        Output::Rust => {
+            // The Debug format of a str is a Rust string literal.
+            let items: Vec<String> = tokens().iter().map(|token| format!("{token:?}")).collect();
+            format!("&[{}]\n", items.join(", "))
+        }
+        Output::C => {
+            let items: Vec<String> = tokens().iter().map(|token| c_string(token)).collect();
+            format!("(char *[]){{{}, NULL}}\n", items.join(", "))
+        }
+        Output::PowerShell => powershell_hashtable(args, arg_options)?,
+        Output::EnvExport => env_exports(args)?,
+        Output::EnvFile => env_file(args)?,
+        Output::GithubWith => github_with(args, arg_options)?,
+    };
+
+    Ok(rendered)
+}
+
+/// Renders every arg as its tokens quoted for the shell, unless the tokens are not
+/// quoted at all or the arg is raw.
+fn shell_words(args: &[Arg], arg_options: &ArgOptions, no_quote: bool) -> Vec<String> {
+    args.iter()
+        .zip(args::token_groups(args, arg_options))
+        .map(|(arg, tokens)| {
+            if no_quote || matches!(arg, Arg::Raw(_)) {
+                tokens.join(" ")
+            } else {
+                shell::join(&tokens)
+            }
+        })
+        .collect()
+}
+
+/// Renders the tokens as an `args: [...]` YAML fragment. YAML takes JSON strings as
+/// they are, which keeps the fragment on one line.
+fn k8s_args(tokens: &[String]) -> String {
+    let items: Vec<String> = tokens
+        .iter()
+        .map(|token| Value::from(token.as_str()).to_string())
+        .collect();
+    format!("args: [{}]\n", items.join(", "))
+}
+
+/// Renders a token as a C string literal. Control characters are written in octal,
+/// which unlike hex escapes cannot swallow the characters following them.
+fn c_string(token: &str) -> String {
+    let mut literal = String::from('"');
+    for c in token.chars() {
+        match c {
+            '"' => literal.push_str(r#"\""#),
+            '\\' => literal.push_str(r"\\"),
+            '\n' => literal.push_str(r"\n"),
+            '\t' => literal.push_str(r"\t"),
+            c if c.is_ascii_control() => literal.push_str(&format!("\\{:03o}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+
+    literal
+}
+
+/// Renders the program and the tokens as an `ExecStart=` line.
+fn exec_start(tokens: &[String], program: Option<&str>) -> anyhow::Result<String> {
+    let program = program.ok_or_else(|| {
+        anyhow::anyhow!("the systemd output requires --program or `{PROGRAM_KEY}`")
+    })?;
+    let tokens: Vec<String> = std::iter::once(program)
+        .chain(tokens.iter().map(String::as_str))
+        .map(systemd_quote)
+        .collect();
+
+    Ok(format!("ExecStart={}\n", tokens.join(" ")))
+}
+
+/// Renders the words as an `ARGS := ...` Makefile variable. The words are quoted for
+/// the shell which runs the recipes, and `$` and `#` are escaped for Make.
+fn make_variable(words: &str) -> String {
+    let value = words.replace('$', "$$").replace('#', r"\#");
+    format!("ARGS := {value}\n")
+}
+
+/// Quotes a token for the command lines of systemd units, which expand `%` specifiers
+/// and `$` variables even in quotes.
+fn systemd_quote(token: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./_-$".contains(c);
+    let escaped = token.replace('%', "%%").replace('$', "$$");
+    if !token.is_empty() && token.chars().all(is_safe) {
+        return escaped;
+    }
+
+    let escaped = escaped
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+        .replace('\t', r"\t");
+    format!("\"{escaped}\"")
+}
+
+/// Renders the options as `@{ key = 'value'; ... }`. Options without values become
+/// switches, and options with several values become arrays.
+fn powershell_hashtable(args: &[Arg], arg_options: &ArgOptions) -> anyhow::Result<String> {
+    let quote = |value: &str| format!("'{}'", value.replace('\'', "''"));
+
+    let mut entries = Vec::new();
+    for arg in args {
+        let Arg::Option { key, values } = arg else {
+            anyhow::bail!("values without a flag cannot be put in a PowerShell hashtable");
+        };
+
+        let name = args::name(key, arg_options);
+        let is_bare_name = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        let name = if is_bare_name { name } else { quote(&name) };
+
+        let value = match values.as_slice() {
+            [] => "$true".to_string(),
+            [value] => quote(value),
+            values => {
+                let values: Vec<String> = values.iter().map(|value| quote(value)).collect();
+                format!("@({})", values.join(", "))
+            }
+        };
+        entries.push(format!("{name} = {value}"));
+    }
+
+    Ok(format!("@{{ {} }}\n", entries.join("; ")))
+}
+
+/// Renders the options as a `with:` mapping for the inputs of a GitHub Actions step.
+/// Inputs are strings, so the values of an array are joined with spaces and every
+/// value is quoted as a JSON string, which YAML takes as it is.
+fn github_with(args: &[Arg], arg_options: &ArgOptions) -> anyhow::Result<String> {
+    let mut lines = String::from("with:\n");
+    for arg in args {
+        let Arg::Option { key, values } = arg else {
+            anyhow::bail!("values without a flag cannot be GitHub Actions inputs");
+        };
+        lines.push_str(&format!(
+            "  {}: {}\n",
+            args::name(key, arg_options),
+            Value::from(values.join(" "))
+        ));
+    }
+
+    Ok(lines)
+}
+
+/// Renders the options as `export KEY=VALUE` lines, quoted for the shell.
+fn env_exports(args: &[Arg]) -> anyhow::Result<String> {
+    let mut exports = String::new();
+    for (name, value) in env_variables(args)? {
+        exports.push_str(&format!("export {name}={}\n", shell::quote(&value)));
+    }
+
+    Ok(exports)
+}
+
+/// Renders the options as `KEY=VALUE` lines for `docker --env-file` and systemd's
+/// `EnvironmentFile=`. Docker takes the values as they are, so they are not quoted.
+fn env_file(args: &[Arg]) -> anyhow::Result<String> {
+    let mut lines = String::new();
+    for (name, value) in env_variables(args)? {
+        if value.contains('\n') {
+            anyhow::bail!("{name}: values in an env file cannot contain newlines");
+        }
+        lines.push_str(&format!("{name}={value}\n"));
+    }
+
+    Ok(lines)
+}
+
+/// Turns the options into environment variables. The values of an array are joined
+/// with spaces, and a flag without values is set to `1`.
+fn env_variables(args: &[Arg]) -> anyhow::Result<Vec<(String, String)>> {
+    args.iter()
+        .map(|arg| match arg {
+            Arg::Option { key, values } if values.is_empty() => {
+                Ok((env_name(key), "1".to_string()))
+            }
+            Arg::Option { key, values } => Ok((env_name(key), values.join(" "))),
+            Arg::Value(_) | Arg::Tokens(_) | Arg::Raw(_) => {
+                anyhow::bail!("values without a flag cannot be turned into environment variables")
+            }
+        })
+        .collect()
+}
+
+/// Turns a key path into an environment variable name, like `MODEL_NUM_LAYERS` for
+/// `model.num-layers`.
+fn env_name(key: &[String]) -> String {
+    key.join("_")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args() -> Vec<Arg> {
+        vec![
+            Arg::Option {
+                key: vec!["name".to_string()],
+                values: vec!["my model".to_string()],
+            },
+            Arg::Value("input.txt".to_string()),
+        ]
+    }
+
+    fn options(format: Output) -> OutputOptions {
+        OutputOptions {
+            format,
+            ..OutputOptions::default()
+        }
+    }
+
+    #[test]
+    fn render_shell() {
+        assert_eq!(
+            render(&args(), &ArgOptions::default(), &options(Output::Shell)).unwrap(),
+            "--name 'my model' input.txt\n"
+        );
+        let no_quote = OutputOptions {
+            no_quote: true,
+            ..OutputOptions::default()
+        };
+        assert_eq!(
+            render(&args(), &ArgOptions::default(), &no_quote).unwrap(),
+            "--name my model input.txt\n"
+        );
+        let args = vec![
+            Arg::Tokens(vec!["-n".to_string(), "a b".to_string()]),
+            Arg::Raw("--vf 'scale=640'".to_string()),
+        ];
+        assert_eq!(
+            render(&args, &ArgOptions::default(), &options(Output::Shell)).unwrap(),
+            "-n 'a b' --vf 'scale=640'\n"
+        );
+    }
+
+    #[test]
+    fn render_pretty_shell() {
+        let options = OutputOptions {
+            pretty: true,
+            ..OutputOptions::default()
+        };
+        assert_eq!(
+            render(&args(), &ArgOptions::default(), &options).unwrap(),
+            "  --name 'my model' \\\n  input.txt\n"
+        );
+    }
+
+    #[test]
+    fn render_print0() {
+        assert_eq!(
+            render(&args(), &ArgOptions::default(), &options(Output::Print0)).unwrap(),
+            "--name\0my model\0input.txt\0"
+        );
+    }
+
+    #[test]
+    fn render_lines() {
+        assert_eq!(
+            render(&args(), &ArgOptions::default(), &options(Output::Lines)).unwrap(),
+            "--name\nmy model\ninput.txt\n"
+        );
+    }
+
+    #[test]
+    fn render_json() {
+        assert_eq!(
+            render(&args(), &ArgOptions::default(), &options(Output::Json)).unwrap(),
+            "[\"--name\",\"my model\",\"input.txt\"]\n"
+        );
+    }
+
+    #[test]
+    fn render_k8s_args() {
+        assert_eq!(
+            render(&args(), &ArgOptions::default(), &options(Output::K8sArgs)).unwrap(),
+            "args: [\"--name\", \"my model\", \"input.txt\"]\n"
+        );
+    }
+
+    #[test]
+    fn render_systemd_exec_start() {
+        let options = OutputOptions {
+            format: Output::Systemd,
+            program: Some("/usr/bin/train".to_string()),
+            ..OutputOptions::default()
+        };
+        let args = vec![
+            Arg::Option {
+                key: vec!["name".to_string()],
+                values: vec!["my \"model\"".to_string()],
+            },
+            Arg::Option {
+                key: vec!["out".to_string()],
+                values: vec!["$HOME/100%".to_string()],
+            },
+        ];
+        assert_eq!(
+            render(&args, &ArgOptions::default(), &options).unwrap(),
+            "ExecStart=/usr/bin/train --name \"my \\\"model\\\"\" --out $$HOME/100%%\n"
+        );
+        assert!(
+            render(
+                &args,
+                &ArgOptions::default(),
+                &self::options(Output::Systemd)
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn render_script_command() {
+        let options = OutputOptions {
+            program: Some("python".to_string()),
+            ..OutputOptions::default()
+        };
+        let args = vec![
+            Arg::Value("train.py".to_string()),
+            Arg::Option {
+                key: vec!["name".to_string()],
+                values: vec!["my model".to_string()],
+            },
+            Arg::Option {
+                key: vec!["layers".to_string()],
+                values: vec!["1".to_string(), "2".to_string()],
+            },
+        ];
+        assert_eq!(
+            script_command(&args, &ArgOptions::default(), &options).unwrap(),
+            "python \\\n  train.py \\\n  --name 'my model' \\\n  --layers 1 2 \\\n  \"$@\"\n"
+        );
+        assert!(script_command(&args, &ArgOptions::default(), &OutputOptions::default()).is_err());
+    }
+
+    #[test]
+    fn write_output_atomically() {
+        let dir = std::env::temp_dir().join("config2args-output");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("args.txt");
+
+        write_atomically(path.to_str().unwrap(), "--lr 0.1\n", false).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "--lr 0.1\n");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        assert!(
+            write_atomically(dir.join("missing/args.txt").to_str().unwrap(), "", false).is_err()
+        );
+    }
+
+    #[test]
+    fn take_program_from_config() {
+        let mut config = serde_json::json!({"_program": "/usr/bin/train", "lr": 0.1});
+        assert_eq!(
+            take_program(&mut config.clone(), None).unwrap().as_deref(),
+            Some("/usr/bin/train")
+        );
+        assert_eq!(
+            take_program(&mut config, Some("/opt/train"))
+                .unwrap()
+                .as_deref(),
+            Some("/opt/train")
+        );
+        assert_eq!(config, serde_json::json!({"lr": 0.1}));
+    }
+
+    #[test]
+    fn render_github_with() {
+        let args = vec![
+            Arg::Option {
+                key: vec!["python-version".to_string()],
+                values: vec!["3.12".to_string()],
+            },
+            Arg::Option {
+                key: vec!["extras".to_string()],
+                values: vec!["dev".to_string(), "test".to_string()],
+            },
+        ];
+        assert_eq!(
+            render(&args, &ArgOptions::default(), &options(Output::GithubWith)).unwrap(),
+            "with:\n  python-version: \"3.12\"\n  extras: \"dev test\"\n"
+        );
+        assert!(
+            render(
+                &self::args(),
+                &ArgOptions::default(),
+                &options(Output::GithubWith)
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn render_makefile_variable() {
+        let args = vec![
+            Arg::Option {
+                key: vec!["out".to_string()],
+                values: vec!["$HOME/logs".to_string()],
+            },
+            Arg::Option {
+                key: vec!["tag".to_string()],
+                values: vec!["#1".to_string()],
+            },
+        ];
+        assert_eq!(
+            render(&args, &ArgOptions::default(), &options(Output::Makefile)).unwrap(),
+            "ARGS := --out '$$HOME/logs' --tag '\\#1'\n"
+        );
+    }
+
+    #[test]
+    fn render_rust_literal() {
+        let args = vec![Arg::Option {
+            key: vec!["sep".to_string()],
+            values: vec!["a \"b\"\n".to_string()],
+        }];
+        assert_eq!(
+            render(&args, &ArgOptions::default(), &options(Output::Rust)).unwrap(),
+            "&[\"--sep\", \"a \\\"b\\\"\\n\"]\n"
+        );
+    }
+
+    #[test]
+    fn render_c_literal() {
+        let args = vec![Arg::Option {
+            key: vec!["sep".to_string()],
+            values: vec!["a \"b\"\n\u{1b}".to_string()],
+        }];
+        assert_eq!(
+            render(&args, &ArgOptions::default(), &options(Output::C)).unwrap(),
+            "(char *[]){\"--sep\", \"a \\\"b\\\"\\n\\033\", NULL}\n"
+        );
+    }
+
+    #[test]
+    fn render_powershell_hashtable() {
+        let args = vec![
+            Arg::Option {
+                key: vec!["Name".to_string()],
+                values: vec!["it's".to_string()],
+            },
+            Arg::Option {
+                key: vec!["model".to_string(), "layers".to_string()],
+                values: vec!["1".to_string(), "2".to_string()],
+            },
+            Arg::Option {
+                key: vec!["Force".to_string()],
+                values: vec![],
+            },
+        ];
+        assert_eq!(
+            render(&args, &ArgOptions::default(), &options(Output::PowerShell)).unwrap(),
+            "@{ Name = 'it''s'; 'model.layers' = @('1', '2'); Force = $true }\n"
+        );
+        assert!(
+            render(
+                &self::args(),
+                &ArgOptions::default(),
+                &options(Output::PowerShell)
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn render_env_exports() {
+        let args = vec![
+            Arg::Option {
+                key: vec!["model".to_string(), "num-layers".to_string()],
+                values: vec!["50".to_string()],
+            },
+            Arg::Option {
+                key: vec!["tags".to_string()],
+                values: vec!["a".to_string(), "b".to_string()],
+            },
+            Arg::Option {
+                key: vec!["verbose".to_string()],
+                values: vec![],
+            },
+        ];
+        assert_eq!(
+            render(&args, &ArgOptions::default(), &options(Output::EnvExport)).unwrap(),
+            "export MODEL_NUM_LAYERS=50\nexport TAGS='a b'\nexport VERBOSE=1\n"
+        );
+        assert!(
+            render(
+                &self::args(),
+                &ArgOptions::default(),
+                &options(Output::EnvExport)
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn render_env_file() {
+        let args = vec![
+            Arg::Option {
+                key: vec!["db".to_string(), "host".to_string()],
+                values: vec!["localhost".to_string()],
+            },
+            Arg::Option {
+                key: vec!["greeting".to_string()],
+                values: vec!["hello world".to_string()],
+            },
+            Arg::Option {
+                key: vec!["verbose".to_string()],
+                values: vec![],
+            },
+        ];
+        assert_eq!(
+            render(&args, &ArgOptions::default(), &options(Output::EnvFile)).unwrap(),
+            "DB_HOST=localhost\nGREETING=hello world\nVERBOSE=1\n"
+        );
+
+        let args = vec![Arg::Option {
+            key: vec!["text".to_string()],
+            values: vec!["a\nb".to_string()],
+        }];
+        assert!(render(&args, &ArgOptions::default(), &options(Output::EnvFile)).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use crate::args::Arg;
+use std::str::FromStr;
+
+/// Rules which map well-known sections of a config to the flags of a tool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    /// `env`, `volumes`, `ports` and `labels` to the flags of `docker run`.
+    Docker,
+}
+
+impl FromStr for Preset {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<Preset> {
+        match name {
+            "docker" => Ok(Preset::Docker),
+            _ => anyhow::bail!("unsupported preset: {name}"),
+        }
+    }
+}
+
+/// Applies the preset to the generated args.
+pub fn apply(args: Vec<Arg>, preset: Preset) -> Vec<Arg> {
+    match preset {
+        Preset::Docker => args.into_iter().flat_map(docker).collect(),
+    }
+}
+
+/// Maps the sections to `docker run` flags. Every item of a section becomes its own
+/// flag. A section is either an array of items as `docker run` takes them, or an
+/// object, whose keys and values are joined with `=` (`env`, `labels`) or `:`
+/// (`volumes`, `ports`).
+fn docker(arg: Arg) -> Vec<Arg> {
+    let Arg::Option { key, values } = &arg else {
+        return vec![arg];
+    };
+    let (flag, joiner) = match key[0].as_str() {
+        "env" => ("e", '='),
+        "volumes" => ("v", ':'),
+        "ports" => ("p", ':'),
+        "labels" => ("label", '='),
+        _ => return vec![arg],
+    };
+
+    let items = if key.len() == 1 {
+        values.clone()
+    } else {
+        vec![format!(
+            "{}{joiner}{}",
+            key[1..].join("."),
+            values.join(" ")
+        )]
+    };
+
+    items
+        .into_iter()
+        .map(|item| Arg::Option {
+            key: vec![flag.to_string()],
+            values: vec![item],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::{self, ArgOptions};
+
+    fn option(key: &[&str], values: &[&str]) -> Arg {
+        Arg::Option {
+            key: key.iter().map(|key| key.to_string()).collect(),
+            values: values.iter().map(|value| value.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn apply_docker_preset() {
+        let args = vec![
+            option(&["name"], &["web"]),
+            option(&["env", "MODE"], &["production"]),
+            option(&["env", "GREETING"], &["hello world"]),
+            option(&["volumes"], &["/data:/data", "/logs:/logs:ro"]),
+            option(&["ports", "8080"], &["80"]),
+            option(&["labels", "team"], &["ml"]),
+            Arg::Value("nginx".to_string()),
+        ];
+        assert_eq!(
+            args::tokens(&apply(args, Preset::Docker), &ArgOptions::default()),
+            vec![
+                "--name",
+                "web",
+                "-e",
+                "MODE=production",
+                "-e",
+                "GREETING=hello world",
+                "-v",
+                "/data:/data",
+                "-v",
+                "/logs:/logs:ro",
+                "-p",
+                "8080:80",
+                "--label",
+                "team=ml",
+                "nginx",
+            ]
+        );
+    }
+}
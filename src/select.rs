@@ -0,0 +1,318 @@
+use glob::Pattern;
+#[cfg(feature = "jq")]
+use jaq_interpret::{Ctx, FilterT, ParseCtx, RcIter, Val};
+use serde_json::Value;
+
+/// Reserved key holding the dotted paths of the keys which a config must have.
+pub const REQUIRED_KEY: &str = "_required";
+
+/// Selects the subtree at a JSON Pointer like `/training/optimizer`.
+pub fn pointer(config: &mut Value, pointer: &str) -> anyhow::Result<Value> {
+    config
+        .pointer_mut(pointer)
+        .map(Value::take)
+        .ok_or_else(|| anyhow::anyhow!("{pointer}: no value is found at the path"))
+}
+
+/// Selects the result of a JMESPath expression like `jobs[0].params`. The objects of
+/// jmespath have sorted keys, so the selected subtrees are taken from the config to
+/// keep the order of their keys, which is the order of the args.
+#[cfg(feature = "jmespath")]
+pub fn jmespath(config: &Value, expression: &str) -> anyhow::Result<Value> {
+    let result = jmespath::compile(expression)?.search(config)?;
+    if result.is_null() {
+        anyhow::bail!("{expression}: the JMESPath expression matches nothing");
+    }
+
+    Ok(keep_order(serde_json::to_value(&*result)?, config))
+}
+
+/// Replaces the result, or else every item or value of it, with the equal subtree of
+/// the config. Objects built by the expression itself keep the sorted keys.
+#[cfg(feature = "jmespath")]
+fn keep_order(result: Value, config: &Value) -> Value {
+    if let Some(subtree) = find_subtree(config, &result) {
+        return subtree.clone();
+    }
+
+    match result {
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| keep_order(item, config))
+                .collect(),
+        ),
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (key, keep_order(value, config)))
+                .collect(),
+        ),
+        result => result,
+    }
+}
+
+/// Finds a subtree of the config equal to the value, regardless of the order of keys.
+#[cfg(feature = "jmespath")]
+fn find_subtree<'a>(config: &'a Value, value: &Value) -> Option<&'a Value> {
+    if config == value {
+        return Some(config);
+    }
+
+    match config {
+        Value::Object(object) => object.values().find_map(|item| find_subtree(item, value)),
+        Value::Array(items) => items.iter().find_map(|item| find_subtree(item, value)),
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "jmespath"))]
+pub fn jmespath(_config: &Value, _expression: &str) -> anyhow::Result<Value> {
+    anyhow::bail!("JMESPath support is disabled; rebuild with `--features jmespath`")
+}
+
+/// Runs a jq filter on the config with the jq standard library. Every output of the
+/// filter becomes a config, so that `.jobs[]` produces a batch of configs.
+#[cfg(feature = "jq")]
+pub fn query(config: Value, filter: &str) -> anyhow::Result<Vec<Value>> {
+    let (main, errors) = jaq_parse::parse(filter, jaq_parse::main());
+    if let Some(error) = errors.first() {
+        anyhow::bail!("invalid query `{filter}`: {error}");
+    }
+    let main = main.ok_or_else(|| anyhow::anyhow!("invalid query `{filter}`"))?;
+
+    let mut defs = ParseCtx::new(Vec::new());
+    defs.insert_natives(jaq_core::core());
+    defs.insert_defs(jaq_std::std());
+    let compiled = defs.compile(main);
+    if !defs.errs.is_empty() {
+        anyhow::bail!("invalid query `{filter}`: undefined filters or variables");
+    }
+
+    let inputs = RcIter::new(core::iter::empty());
+    compiled
+        .run((Ctx::new([], &inputs), Val::from(config)))
+        .map(|output| {
+            output
+                .map(Value::from)
+                .map_err(|error| anyhow::anyhow!("query `{filter}` failed: {error}"))
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "jq"))]
+pub fn query(_config: Value, _filter: &str) -> anyhow::Result<Vec<Value>> {
+    anyhow::bail!("jq support is disabled; rebuild with `--features jq`")
+}
+
+/// Removes the keys whose dotted paths, like `debug.verbose`, match any of the glob
+/// patterns, like `debug.*`.
+pub fn exclude(config: &mut Value, patterns: &[String]) -> anyhow::Result<()> {
+    let patterns = compile(patterns)?;
+    exclude_paths(config, "", &patterns);
+
+    Ok(())
+}
+
+fn exclude_paths(config: &mut Value, prefix: &str, patterns: &[Pattern]) {
+    let Value::Object(object) = config else {
+        return;
+    };
+    object.retain(|key, value| {
+        let path = join_path(prefix, key);
+        if patterns.iter().any(|pattern| pattern.matches(&path)) {
+            return false;
+        }
+        exclude_paths(value, &path, patterns);
+        true
+    });
+}
+
+/// Fails unless the config has every key listed in its `_required` array and in
+/// `required`, like `data.path`, with a value other than null. The `_required` array is
+/// removed from the config.
+pub fn require(config: &mut Value, required: &[String]) -> anyhow::Result<()> {
+    let mut paths = required.to_vec();
+    let listed = match config {
+        Value::Object(object) => object.shift_remove(REQUIRED_KEY),
+        _ => None,
+    };
+    if let Some(listed) = listed {
+        let listed: Option<Vec<String>> = listed.as_array().and_then(|items| {
+            items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect()
+        });
+        let listed = listed
+            .ok_or_else(|| anyhow::anyhow!("{REQUIRED_KEY} must be an array of key paths"))?;
+        paths.extend(listed);
+    }
+
+    let missing: Vec<&str> = paths
+        .iter()
+        .filter(|path| {
+            let value = path
+                .split('.')
+                .try_fold(&*config, |value, key| value.get(key));
+            value.is_none_or(Value::is_null)
+        })
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        anyhow::bail!("missing required keys: {}", missing.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Keeps only the keys whose dotted paths, or the paths of their parents, match any of
+/// the glob patterns, like `training.*`. No keys are removed without patterns.
+pub fn only(config: &mut Value, patterns: &[String]) -> anyhow::Result<()> {
+    if patterns.is_empty() {
+        return Ok(());
+    }
+    let patterns = compile(patterns)?;
+    retain_paths(config, "", &patterns);
+
+    Ok(())
+}
+
+/// Keeps the matching keys and the objects containing them, and returns whether any
+/// key is kept.
+fn retain_paths(config: &mut Value, prefix: &str, patterns: &[Pattern]) -> bool {
+    let Value::Object(object) = config else {
+        return false;
+    };
+    object.retain(|key, value| {
+        let path = join_path(prefix, key);
+        patterns.iter().any(|pattern| pattern.matches(&path))
+            || retain_paths(value, &path, patterns)
+    });
+
+    !object.is_empty()
+}
+
+fn compile(patterns: &[String]) -> anyhow::Result<Vec<Pattern>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Pattern::new(pattern)
+                .map_err(|error| anyhow::anyhow!("invalid key pattern `{pattern}`: {error}"))
+        })
+        .collect()
+}
+
+fn join_path(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn select_subtree_with_pointer() {
+        let mut config = json!({"training": {"optimizer": {"lr": 0.1}}, "serving": {"port": 80}});
+        assert_eq!(
+            pointer(&mut config, "/training/optimizer").unwrap(),
+            json!({"lr": 0.1})
+        );
+        assert!(pointer(&mut config, "/training/scheduler").is_err());
+    }
+
+    #[cfg(feature = "jq")]
+    #[test]
+    fn transform_with_query() {
+        let config = json!({"jobs": [{"lr": 0.1}, {"lr": 0.01}], "out": "logs"});
+        assert_eq!(
+            query(config.clone(), ".jobs[] | {learning_rate: .lr}").unwrap(),
+            vec![
+                json!({"learning_rate": 0.1}),
+                json!({"learning_rate": 0.01})
+            ]
+        );
+        assert_eq!(
+            query(config.clone(), "del(.jobs)").unwrap(),
+            vec![json!({"out": "logs"})]
+        );
+        assert!(query(config.clone(), ".jobs[").is_err());
+        assert!(query(config, "undefined_filter").is_err());
+    }
+
+    #[test]
+    fn exclude_keys() {
+        let mut config = json!({"lr": 0.1, "debug": {"verbose": true, "trace": 1}, "seed": 1});
+        exclude(&mut config, &["debug.*".to_string(), "seed".to_string()]).unwrap();
+        assert_eq!(config, json!({"lr": 0.1, "debug": {}}));
+        assert!(exclude(&mut config, &["[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn require_keys() {
+        let mut config =
+            json!({"_required": ["model"], "model": "resnet", "data": {"path": "/data"}});
+        require(&mut config, &["data.path".to_string()]).unwrap();
+        assert_eq!(
+            config,
+            json!({"model": "resnet", "data": {"path": "/data"}})
+        );
+
+        let mut config = json!({"_required": ["model", "data.path", "seed"], "seed": null});
+        let error = require(&mut config, &[]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "missing required keys: model, data.path, seed"
+        );
+        assert!(require(&mut json!({"_required": "model"}), &[]).is_err());
+    }
+
+    #[test]
+    fn keep_only_keys() {
+        let mut config = json!({
+            "training": {"lr": 0.1, "epochs": 10},
+            "serving": {"port": 80, "lr": 0.2},
+            "seed": 1,
+            "debug": true
+        });
+        only(&mut config, &["training.*".to_string(), "seed".to_string()]).unwrap();
+        assert_eq!(
+            config,
+            json!({"training": {"lr": 0.1, "epochs": 10}, "seed": 1})
+        );
+
+        let mut config = json!({"lr": 0.1});
+        only(&mut config, &[]).unwrap();
+        assert_eq!(config, json!({"lr": 0.1}));
+    }
+
+    #[cfg(feature = "jmespath")]
+    #[test]
+    fn select_with_jmespath() {
+        let config = json!({"jobs": [{"params": {"lr": 0.1}}, {"params": {"lr": 0.01}}]});
+        assert_eq!(
+            jmespath(&config, "jobs[1].params").unwrap(),
+            json!({"lr": 0.01})
+        );
+        assert!(jmespath(&config, "jobs[2].params").is_err());
+    }
+
+    #[cfg(feature = "jmespath")]
+    #[test]
+    fn select_with_jmespath_in_the_order_of_the_config() {
+        let config = json!({"jobs": [{"params": {"b": 1, "a": 2}}, {"params": {"d": 3, "c": 4}}]});
+        assert_eq!(
+            jmespath(&config, "jobs[0].params").unwrap().to_string(),
+            r#"{"b":1,"a":2}"#
+        );
+        assert_eq!(
+            jmespath(&config, "jobs[*].params").unwrap().to_string(),
+            r#"[{"b":1,"a":2},{"d":3,"c":4}]"#
+        );
+    }
+}
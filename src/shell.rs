@@ -0,0 +1,87 @@
+use std::borrow::Cow;
+
+/// Quotes a token for POSIX shells. Tokens made only of characters which are never
+/// special to the shell are returned as they are.
+pub fn quote(token: &str) -> Cow<'_, str> {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c);
+    if !token.is_empty() && token.chars().all(is_safe) {
+        return Cow::Borrowed(token);
+    }
+
+    Cow::Owned(format!("'{}'", token.replace('\'', r"'\''")))
+}
+
+/// Quotes the tokens and joins them with spaces, so that `eval` gets them back.
+pub fn join(tokens: &[String]) -> String {
+    tokens
+        .iter()
+        .map(|token| quote(token))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Splits a line into words at whitespace, which is the inverse of `join` for words
+/// quoted with single quotes. A backslash outside quotes escapes the next character.
+pub fn split(line: &str) -> anyhow::Result<Vec<String>> {
+    let mut words = Vec::new();
+    // `None` between words, so that `''` makes an empty word.
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => anyhow::bail!("unterminated quote in `{line}`"),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_tokens() {
+        assert_eq!(quote("--out"), "--out");
+        assert_eq!(quote("logs/a-1.0,b=2"), "logs/a-1.0,b=2");
+        assert_eq!(quote(""), "''");
+        assert_eq!(quote("a b"), "'a b'");
+        assert_eq!(quote("$HOME"), "'$HOME'");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn join_tokens() {
+        let tokens = vec!["--name".to_string(), "my model".to_string()];
+        assert_eq!(join(&tokens), "--name 'my model'");
+    }
+
+    #[test]
+    fn split_line() {
+        let tokens = vec![
+            "--name".to_string(),
+            "my model".to_string(),
+            "it's".to_string(),
+            String::new(),
+        ];
+        assert_eq!(split(&join(&tokens)).unwrap(), tokens);
+        assert_eq!(
+            split("  1 2\\ 3 \"a\"\n").unwrap(),
+            vec!["1", "2 3", "\"a\""]
+        );
+        assert!(split("'a").is_err());
+    }
+}
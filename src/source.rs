@@ -0,0 +1,378 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::{Value, json};
+use std::env;
+use std::io::{Read, Write};
+use std::process::{Command, Output, Stdio};
+
+/// Path which makes config2args read the config from stdin.
+pub const STDIN: &str = "-";
+
+/// Environment variable holding a bearer token sent with HTTP(S) requests.
+pub const BEARER_TOKEN_ENV: &str = "CONFIG2ARGS_BEARER_TOKEN";
+
+/// Reads the raw contents of a config source.
+pub fn read(path: &str) -> anyhow::Result<Vec<u8>> {
+    if path == STDIN {
+        let mut contents = Vec::new();
+        std::io::stdin().read_to_end(&mut contents)?;
+        return Ok(contents);
+    }
+
+    if path.starts_with("http://") || path.starts_with("https://") {
+        return fetch(path);
+    }
+
+    if path.starts_with("s3://") || path.starts_with("gs://") {
+        return fetch_object(path);
+    }
+
+    if ["consul://", "consul+https://", "etcd://", "etcd+https://"]
+        .iter()
+        .any(|scheme| path.starts_with(scheme))
+    {
+        return fetch_key(path);
+    }
+
+    if let Some(location) = path.strip_prefix("k8s://") {
+        return fetch_config_map(path, location);
+    }
+
+    if let Some(location) = path.strip_prefix("git://") {
+        return read_git_object(path, location);
+    }
+
+    Ok(std::fs::read(path)?)
+}
+
+/// Decompresses gzip and zstd compressed contents, which are detected by their magic
+/// numbers. Other contents are returned as they are.
+pub fn decompress(path: &str, contents: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    if contents.starts_with(&[0x1f, 0x8b]) {
+        flate2::read::MultiGzDecoder::new(contents.as_slice())
+            .read_to_end(&mut decompressed)
+            .map_err(|error| anyhow::anyhow!("{path}: failed to decompress gzip: {error}"))?;
+    } else if contents.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        decompressed = zstd::decode_all(contents.as_slice())
+            .map_err(|error| anyhow::anyhow!("{path}: failed to decompress zstd: {error}"))?;
+    } else {
+        return Ok(contents);
+    }
+
+    Ok(decompressed)
+}
+
+/// Expands a glob pattern into the matching local files in lexical order. Any other
+/// path is returned as it is.
+pub fn expand_glob(path: &str) -> anyhow::Result<Vec<String>> {
+    if !is_local_file(path) || !path.contains(['*', '?', '[']) {
+        return Ok(vec![path.to_string()]);
+    }
+
+    let mut paths = Vec::new();
+    for entry in glob::glob(path)? {
+        paths.push(entry?.to_string_lossy().to_string());
+    }
+    if paths.is_empty() {
+        anyhow::bail!("{path}: no files match the pattern");
+    }
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Returns whether the path points to a file on the local file system.
+pub fn is_local_file(path: &str) -> bool {
+    path != STDIN && !path.contains("://")
+}
+
+fn fetch(url: &str) -> anyhow::Result<Vec<u8>> {
+    let mut request = ureq::get(url);
+    if let Ok(token) = env::var(BEARER_TOKEN_ENV) {
+        request = request.set("Authorization", &format!("Bearer {token}"));
+    }
+
+    read_response(url, request.call())
+}
+
+fn read_response(
+    url: &str,
+    response: Result<ureq::Response, ureq::Error>,
+) -> anyhow::Result<Vec<u8>> {
+    let mut contents = Vec::new();
+    response
+        .map_err(|error| anyhow::anyhow!("{url}: {error}"))?
+        .into_reader()
+        .read_to_end(&mut contents)?;
+
+    Ok(contents)
+}
+
+/// Reads the value of a key from Consul (`consul://host[:port]/key`) or from etcd
+/// through its JSON gateway (`etcd://host[:port]/key`). The `consul+https` and
+/// `etcd+https` schemes connect with TLS. etcd keys keep their leading `/`.
+fn fetch_key(uri: &str) -> anyhow::Result<Vec<u8>> {
+    let (scheme, rest) = uri.split_once("://").unwrap_or_default();
+    let (scheme, protocol) = match scheme.strip_suffix("+https") {
+        Some(scheme) => (scheme, "https"),
+        None => (scheme, "http"),
+    };
+    let (host, key) = rest
+        .find('/')
+        .map(|position| rest.split_at(position))
+        .filter(|(_, key)| key.len() > 1)
+        .ok_or_else(|| anyhow::anyhow!("{uri}: key is missing"))?;
+
+    if scheme == "consul" {
+        let host = with_default_port(host, 8500);
+        let key = percent_encode(&key[1..]);
+        let mut request = ureq::get(&format!("{protocol}://{host}/v1/kv/{key}?raw"));
+        if let Ok(token) = env::var("CONSUL_HTTP_TOKEN") {
+            request = request.set("X-Consul-Token", &token);
+        }
+        return read_response(uri, request.call());
+    }
+
+    let host = with_default_port(host, 2379);
+    let body = json!({"key": BASE64.encode(key)}).to_string();
+    let response = read_response(
+        uri,
+        ureq::post(&format!("{protocol}://{host}/v3/kv/range")).send_string(&body),
+    )?;
+    let response: Value = serde_json::from_slice(&response)?;
+    let value = response["kvs"][0]["value"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("{uri}: key is not found"))?;
+
+    Ok(BASE64.decode(value)?)
+}
+
+/// Percent-encodes a key for a URL path, keeping `/` as the separator.
+fn percent_encode(key: &str) -> String {
+    let mut encoded = String::new();
+    for byte in key.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    encoded
+}
+
+/// Reads a key of a ConfigMap (`k8s://namespace/configmap/key`) from the cluster API
+/// with `kubectl`, so that both kubeconfig and in-cluster credentials work. `kubectl`
+/// must be in PATH.
+fn fetch_config_map(uri: &str, location: &str) -> anyhow::Result<Vec<u8>> {
+    let parts: Vec<&str> = location.splitn(3, '/').collect();
+    let [namespace, name, key] = parts[..] else {
+        anyhow::bail!("{uri}: expected k8s://namespace/configmap/key");
+    };
+
+    let mut command = Command::new("kubectl");
+    command.args([
+        "get",
+        "configmap",
+        name,
+        "--namespace",
+        namespace,
+        "--output",
+        "json",
+    ]);
+    let config_map: Value = serde_json::from_slice(&run(uri, &mut command)?)?;
+
+    if let Some(value) = config_map["data"][key].as_str() {
+        return Ok(value.as_bytes().to_vec());
+    }
+    if let Some(value) = config_map["binaryData"][key].as_str() {
+        return Ok(BASE64.decode(value)?);
+    }
+
+    anyhow::bail!("{uri}: key `{key}` is not found in the ConfigMap")
+}
+
+/// Reads a file as it exists at a git ref (`git://repo#ref:path/to/config.json`),
+/// where `repo` is the path to a local repository.
+fn read_git_object(uri: &str, location: &str) -> anyhow::Result<Vec<u8>> {
+    let (repository, object) = location
+        .split_once('#')
+        .filter(|(_, object)| object.contains(':'))
+        .ok_or_else(|| anyhow::anyhow!("{uri}: expected git://repo#ref:path"))?;
+    // A ref starting with `-` would be taken for an option of `git show`.
+    if object.starts_with('-') {
+        anyhow::bail!("{uri}: a ref must not start with `-`");
+    }
+
+    let mut command = Command::new("git");
+    command.args(["-C", repository, "show", object]);
+
+    run(uri, &mut command)
+}
+
+/// Runs an external command and returns its stdout.
+fn run(uri: &str, command: &mut Command) -> anyhow::Result<Vec<u8>> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let output = command.output().map_err(|error| match error.kind() {
+        std::io::ErrorKind::NotFound => {
+            anyhow::anyhow!("{uri}: {program} is required but is not found in PATH")
+        }
+        _ => anyhow::anyhow!("{uri}: failed to run {program}: {error}"),
+    })?;
+
+    into_stdout(uri, &program, output)
+}
+
+/// Runs an external command with the input written to its stdin and returns its stdout.
+pub fn run_with_input(uri: &str, command: &mut Command, input: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| anyhow::anyhow!("{uri}: failed to run {program}: {error}"))?;
+
+    // Write the input from another thread, so that a command which writes before it
+    // reads all of its input does not block on a full pipe.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let output = std::thread::scope(|scope| {
+        let writer = scope.spawn(move || stdin.write_all(input));
+        let output = child.wait_with_output();
+        // The command may exit without reading all of its input, which is reported by
+        // its exit status rather than by the broken pipe.
+        let _ = writer.join();
+        output
+    })?;
+
+    into_stdout(uri, &program, output)
+}
+
+fn into_stdout(uri: &str, program: &str, output: Output) -> anyhow::Result<Vec<u8>> {
+    if !output.status.success() {
+        anyhow::bail!(
+            "{uri}: {program} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(output.stdout)
+}
+
+fn with_default_port(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{host}:{port}")
+    }
+}
+
+/// Downloads an object from S3 or GCS with the credentials found in the environment.
+#[cfg(feature = "cloud")]
+fn fetch_object(uri: &str) -> anyhow::Result<Vec<u8>> {
+    use object_store::ObjectStore;
+    use object_store::aws::AmazonS3Builder;
+    use object_store::gcp::GoogleCloudStorageBuilder;
+
+    let key = uri
+        .split_once("://")
+        .and_then(|(_, rest)| rest.split_once('/'))
+        .map(|(_, key)| key)
+        .ok_or_else(|| anyhow::anyhow!("{uri}: object key is missing"))?;
+
+    let store: Box<dyn ObjectStore> = if uri.starts_with("s3://") {
+        Box::new(AmazonS3Builder::from_env().with_url(uri).build()?)
+    } else {
+        Box::new(
+            GoogleCloudStorageBuilder::from_env()
+                .with_url(uri)
+                .build()?,
+        )
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let contents = runtime.block_on(async {
+        store
+            .get(&object_store::path::Path::from(key))
+            .await?
+            .bytes()
+            .await
+    })?;
+
+    Ok(contents.to_vec())
+}
+
+#[cfg(not(feature = "cloud"))]
+fn fetch_object(uri: &str) -> anyhow::Result<Vec<u8>> {
+    anyhow::bail!("{uri}: S3 and GCS support is disabled; rebuild with `--features cloud`")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_default_port() {
+        assert_eq!(with_default_port("localhost", 8500), "localhost:8500");
+        assert_eq!(with_default_port("consul:8501", 8500), "consul:8501");
+    }
+
+    #[test]
+    fn reject_git_refs_like_options() {
+        assert!(read_git_object("git://.#--output=/tmp/x:y", ".#--output=/tmp/x:y").is_err());
+    }
+
+    #[test]
+    fn percent_encode_keys() {
+        assert_eq!(percent_encode("configs/train"), "configs/train");
+        assert_eq!(
+            percent_encode("configs/my train?v=1"),
+            "configs/my%20train%3Fv%3D1"
+        );
+    }
+
+    #[test]
+    fn decompress_contents() {
+        let config = br#"{"lr": 0.1}"#;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(config).unwrap();
+        let gzip = encoder.finish().unwrap();
+        assert_eq!(decompress("config.json.gz", gzip).unwrap(), config);
+
+        let zstd = zstd::encode_all(config.as_slice(), 0).unwrap();
+        assert_eq!(decompress("config.json.zst", zstd).unwrap(), config);
+
+        assert_eq!(decompress("config.json", config.to_vec()).unwrap(), config);
+    }
+
+    #[test]
+    fn expand_glob_patterns() {
+        let dir = env::temp_dir().join("config2args-glob");
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["20-override.json", "10-base.json", "notes.txt"] {
+            std::fs::write(dir.join(name), "{}").unwrap();
+        }
+
+        let pattern = format!("{}/*.json", dir.display());
+        assert_eq!(
+            expand_glob(&pattern).unwrap(),
+            vec![
+                format!("{}/10-base.json", dir.display()),
+                format!("{}/20-override.json", dir.display()),
+            ]
+        );
+        assert_eq!(expand_glob("config.json").unwrap(), vec!["config.json"]);
+        assert!(expand_glob(&format!("{}/*.yml", dir.display())).is_err());
+    }
+
+    #[test]
+    fn local_file_paths() {
+        assert!(is_local_file("config.json"));
+        assert!(!is_local_file("-"));
+        assert!(!is_local_file("consul://localhost/train"));
+    }
+}
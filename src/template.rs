@@ -0,0 +1,439 @@
+use crate::args::KeyCase;
+use crate::shell;
+use chrono::Local;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use tera::Tera;
+
+/// A filter producing args from a value, registered in every template engine.
+type Filter = fn(&Value) -> Result<Value, String>;
+
+const FILTERS: [(&str, Filter); 4] = [
+    ("shell_quote", shell_quote),
+    ("kebab", kebab),
+    ("basename", basename),
+    ("join_args", join_args),
+];
+
+/// The engine which renders templates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Engine {
+    #[default]
+    Tera,
+    Handlebars,
+    MiniJinja,
+}
+
+impl FromStr for Engine {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<Engine> {
+        match name {
+            "tera" => Ok(Engine::Tera),
+            "handlebars" => Ok(Engine::Handlebars),
+            "minijinja" => Ok(Engine::MiniJinja),
+            _ => anyhow::bail!("unsupported template engine: {name}"),
+        }
+    }
+}
+
+impl Engine {
+    /// Infers the engine from the extension of a template, like Handlebars for
+    /// `launch.hbs`. Returns `None` for a path which is not a template.
+    pub fn from_path(path: &str) -> Option<Engine> {
+        if path.ends_with(".tera") {
+            Some(Engine::Tera)
+        } else if path.ends_with(".hbs") {
+            Some(Engine::Handlebars)
+        } else if path.ends_with(".j2") {
+            Some(Engine::MiniJinja)
+        } else {
+            None
+        }
+    }
+}
+
+/// The templates of an engine with the filters of this module. The values are not
+/// HTML-escaped, since they are args.
+pub enum Templates {
+    Tera(Tera),
+    #[cfg(feature = "handlebars")]
+    Handlebars(Box<handlebars::Handlebars<'static>>),
+    #[cfg(feature = "minijinja")]
+    MiniJinja(minijinja::Environment<'static>),
+}
+
+impl Templates {
+    /// Creates the templates of the engine, which loads the templates under the template
+    /// directory so that they can include and extend each other. Tera and MiniJinja name
+    /// them by their paths relative to the directory, and Handlebars by the paths of the
+    /// `.hbs` files without the extension.
+    ///
+    /// Besides the filters, `now()` returns the current time, `date` formats a time like
+    /// `now() | date(format="%Y%m%d")`, and `run_id()` returns the time when the
+    /// templates were created, like `20240323-005419-123`, which is the same for every
+    /// template of a run and sorts in the order of the runs.
+    pub fn new(engine: Engine, template_dir: Option<&str>) -> anyhow::Result<Templates> {
+        let run_id = Local::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+        match engine {
+            Engine::Tera => {
+                let mut tera = match template_dir {
+                    Some(dir) => Tera::new(&format!("{}/**/*", dir.trim_end_matches('/')))?,
+                    None => Tera::default(),
+                };
+                // The output is args, so HTML escaping would only corrupt values like
+                // paths, which Tera escapes `/` in.
+                tera.autoescape_on(Vec::new());
+                for (name, filter) in FILTERS {
+                    tera.register_filter(name, move |value: &Value, _: &HashMap<String, Value>| {
+                        filter(value).map_err(tera::Error::msg)
+                    });
+                }
+                // `now()` and `date` are built into tera.
+                tera.register_function("run_id", move |_: &HashMap<String, Value>| {
+                    Ok(Value::from(run_id.clone()))
+                });
+                Ok(Templates::Tera(tera))
+            }
+            #[cfg(feature = "handlebars")]
+            Engine::Handlebars => {
+                let mut handlebars = handlebars::Handlebars::new();
+                handlebars.register_escape_fn(handlebars::no_escape);
+                if let Some(dir) = template_dir {
+                    handlebars.register_templates_directory(dir, Default::default())?;
+                }
+                for (name, filter) in FILTERS {
+                    handlebars.register_helper(
+                        name,
+                        Box::new(Helper(Box::new(move |params: &[&Value]| {
+                            filter(params.first().copied().unwrap_or(&Value::Null))
+                        }))),
+                    );
+                }
+                // Like `{{date (now) "%Y%m%d"}}`.
+                handlebars
+                    .register_helper("now", Box::new(Helper(Box::new(|_: &[&Value]| Ok(now())))));
+                handlebars.register_helper(
+                    "date",
+                    Box::new(Helper(Box::new(|params: &[&Value]| match params {
+                        [time, Value::String(format)] => format_date(time, format),
+                        _ => Err("helper `date` expects a time and a format".to_string()),
+                    }))),
+                );
+                handlebars.register_helper(
+                    "run_id",
+                    Box::new(Helper(Box::new(move |_: &[&Value]| {
+                        Ok(Value::from(run_id.clone()))
+                    }))),
+                );
+                Ok(Templates::Handlebars(Box::new(handlebars)))
+            }
+            #[cfg(not(feature = "handlebars"))]
+            Engine::Handlebars => anyhow::bail!(
+                "Handlebars support is disabled; rebuild with `--features handlebars`"
+            ),
+            #[cfg(feature = "minijinja")]
+            Engine::MiniJinja => {
+                let mut environment = minijinja::Environment::new();
+                environment.set_auto_escape_callback(|_| minijinja::AutoEscape::None);
+                if let Some(dir) = template_dir {
+                    environment.set_loader(minijinja::path_loader(dir));
+                }
+                for (name, filter) in FILTERS {
+                    environment.add_filter(name, move |value: minijinja::Value| {
+                        let value = serde_json::to_value(&value).map_err(minijinja_error)?;
+                        filter(&value)
+                            .map(|result| minijinja::Value::from_serialize(&result))
+                            .map_err(minijinja_error)
+                    });
+                }
+                environment.add_function("now", || minijinja::Value::from_serialize(now()));
+                environment.add_filter(
+                    "date",
+                    |time: minijinja::Value, kwargs: minijinja::value::Kwargs| {
+                        let time = serde_json::to_value(&time).map_err(minijinja_error)?;
+                        let format: String = kwargs.get("format")?;
+                        kwargs.assert_all_used()?;
+                        format_date(&time, &format)
+                            .map(|date| minijinja::Value::from_serialize(&date))
+                            .map_err(minijinja_error)
+                    },
+                );
+                environment.add_function("run_id", move || run_id.clone());
+                Ok(Templates::MiniJinja(environment))
+            }
+            #[cfg(not(feature = "minijinja"))]
+            Engine::MiniJinja => {
+                anyhow::bail!("MiniJinja support is disabled; rebuild with `--features minijinja`")
+            }
+        }
+    }
+
+    /// Renders a template given as a string with the context.
+    pub fn render_str(&mut self, template: &str, context: &Value) -> anyhow::Result<String> {
+        match self {
+            Templates::Tera(tera) => {
+                Ok(tera.render_str(template, &tera::Context::from_value(context.clone())?)?)
+            }
+            #[cfg(feature = "handlebars")]
+            Templates::Handlebars(handlebars) => Ok(handlebars.render_template(template, context)?),
+            #[cfg(feature = "minijinja")]
+            Templates::MiniJinja(environment) => Ok(environment.render_str(template, context)?),
+        }
+    }
+
+    /// Renders the template named in the template directory with the context.
+    pub fn render(&self, name: &str, context: &Value) -> anyhow::Result<String> {
+        match self {
+            Templates::Tera(tera) => {
+                Ok(tera.render(name, &tera::Context::from_value(context.clone())?)?)
+            }
+            #[cfg(feature = "handlebars")]
+            Templates::Handlebars(handlebars) => Ok(handlebars.render(name, context)?),
+            #[cfg(feature = "minijinja")]
+            Templates::MiniJinja(environment) => {
+                Ok(environment.get_template(name)?.render(context)?)
+            }
+        }
+    }
+}
+
+/// A function of the params as a Handlebars helper, like `{{shell_quote name}}`.
+#[cfg(feature = "handlebars")]
+#[allow(clippy::type_complexity)]
+struct Helper(Box<dyn Fn(&[&Value]) -> Result<Value, String> + Send + Sync>);
+
+#[cfg(feature = "handlebars")]
+impl handlebars::HelperDef for Helper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        helper: &handlebars::Helper<'rc>,
+        _: &'reg handlebars::Handlebars<'reg>,
+        _: &'rc handlebars::Context,
+        _: &mut handlebars::RenderContext<'reg, 'rc>,
+    ) -> Result<handlebars::ScopedJson<'rc>, handlebars::RenderError> {
+        let params: Vec<&Value> = helper.params().iter().map(|param| param.value()).collect();
+        let result = (self.0)(&params).map_err(handlebars::RenderErrorReason::Other)?;
+        Ok(handlebars::ScopedJson::Derived(result))
+    }
+}
+
+#[cfg(feature = "minijinja")]
+fn minijinja_error(error: impl ToString) -> minijinja::Error {
+    minijinja::Error::new(minijinja::ErrorKind::InvalidOperation, error.to_string())
+}
+
+// Tera has its own `now()` and `date`, which the other engines get from these.
+
+/// Returns the current local time in RFC 3339, like `2024-03-23T00:54:19.123+09:00`.
+#[cfg(any(feature = "handlebars", feature = "minijinja"))]
+fn now() -> Value {
+    Value::from(Local::now().to_rfc3339())
+}
+
+/// Formats a time in the strftime format, like `20240323` for `%Y%m%d`. The time is a
+/// timestamp in seconds, or a date or time in RFC 3339 like `now()` returns.
+#[cfg(any(feature = "handlebars", feature = "minijinja", test))]
+fn format_date(time: &Value, format: &str) -> Result<Value, String> {
+    use chrono::{DateTime, FixedOffset, NaiveDate};
+    use std::fmt::Write;
+
+    let time: DateTime<FixedOffset> = match time {
+        Value::Number(seconds) => seconds
+            .as_i64()
+            .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+            .map(|time| time.fixed_offset()),
+        Value::String(text) => DateTime::parse_from_rfc3339(text).ok().or_else(|| {
+            let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+            Some(date.and_hms_opt(0, 0, 0)?.and_utc().fixed_offset())
+        }),
+        _ => None,
+    }
+    .ok_or_else(|| format!("`date` expects a timestamp or a time in RFC 3339: {time}"))?;
+
+    let mut date = String::new();
+    write!(date, "{}", time.format(format))
+        .map_err(|_| format!("invalid date format: {format}"))?;
+    Ok(Value::from(date))
+}
+
+/// Quotes a value for POSIX shells, like `'my model'` for `my model`.
+fn shell_quote(value: &Value) -> Result<Value, String> {
+    let token = to_token("shell_quote", value)?;
+    Ok(Value::from(shell::quote(&token).into_owned()))
+}
+
+/// Rewrites a key in kebab case, like `batch-size` for `batchSize`.
+fn kebab(value: &Value) -> Result<Value, String> {
+    let key = to_token("kebab", value)?;
+    Ok(Value::from(KeyCase::Kebab.apply(&key)))
+}
+
+/// Returns the last component of a path, like `model.pt` for `/data/model.pt`.
+fn basename(value: &Value) -> Result<Value, String> {
+    let path = to_token("basename", value)?;
+    let name = Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(Value::from(name))
+}
+
+/// Quotes every item of an array for POSIX shells and joins them with spaces, like
+/// `a 'b c'` for `["a", "b c"]`.
+fn join_args(value: &Value) -> Result<Value, String> {
+    let items = value
+        .as_array()
+        .ok_or_else(|| "filter `join_args` expects an array".to_string())?;
+    let tokens = items
+        .iter()
+        .map(|item| to_token("join_args", item))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Value::from(shell::join(&tokens)))
+}
+
+/// Converts a string, number or boolean into a token for a filter.
+fn to_token(filter: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(value) => Ok(value.clone()),
+        Value::Number(_) | Value::Bool(_) => Ok(value.to_string()),
+        _ => Err(format!(
+            "filter `{filter}` expects a string, a number or a boolean: {value}"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn context() -> Value {
+        json!({
+            "name": "my model",
+            "key": "batchSize",
+            "path": "/data/model.pt",
+            "files": ["a.txt", "b c.txt", 1]
+        })
+    }
+
+    #[test]
+    fn filters() {
+        let mut templates = Templates::new(Engine::Tera, None).unwrap();
+        assert_eq!(
+            templates
+                .render_str(
+                    "{{ name | shell_quote }} --{{ key | kebab }} {{ path | basename }} {{ files | join_args }}",
+                    &context()
+                )
+                .unwrap(),
+            "'my model' --batch-size model.pt a.txt 'b c.txt' 1"
+        );
+        assert!(
+            templates
+                .render_str("{{ files | kebab }}", &context())
+                .is_err()
+        );
+        assert!(
+            templates
+                .render_str("{{ name | join_args }}", &context())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn no_html_escaping() {
+        let mut templates = Templates::new(Engine::Tera, None).unwrap();
+        assert_eq!(
+            templates
+                .render_str("{{ out }}", &json!({"out": "logs/a&b"}))
+                .unwrap(),
+            "logs/a&b"
+        );
+    }
+
+    #[test]
+    fn dates() {
+        let mut templates = Templates::new(Engine::Tera, None).unwrap();
+        let run_id = templates.render_str("{{ run_id() }}", &context()).unwrap();
+        assert_eq!(run_id.len(), "20240323-005419-123".len());
+        assert_eq!(
+            templates.render_str("{{ run_id() }}", &context()).unwrap(),
+            run_id
+        );
+        assert!(
+            templates
+                .render_str("{{ now() | date(format=\"%Y\") }}", &context())
+                .is_ok()
+        );
+
+        assert_eq!(
+            format_date(&json!(0), "%Y%m%d-%H%M").unwrap(),
+            json!("19700101-0000")
+        );
+        assert_eq!(
+            format_date(&json!("2024-03-23T00:54:19+09:00"), "%Y%m%d-%H%M").unwrap(),
+            json!("20240323-0054")
+        );
+        assert_eq!(
+            format_date(&json!("2024-03-23"), "%Y/%m/%d").unwrap(),
+            json!("2024/03/23")
+        );
+        assert!(format_date(&json!("yesterday"), "%Y").is_err());
+        assert!(format_date(&json!(0), "%Q").is_err());
+    }
+
+    #[test]
+    fn parse_engines() {
+        assert_eq!("tera".parse::<Engine>().unwrap(), Engine::Tera);
+        assert_eq!("handlebars".parse::<Engine>().unwrap(), Engine::Handlebars);
+        assert_eq!("minijinja".parse::<Engine>().unwrap(), Engine::MiniJinja);
+        assert!("mustache".parse::<Engine>().is_err());
+    }
+
+    #[test]
+    fn engines_from_paths() {
+        assert_eq!(Engine::from_path("config.json.tera"), Some(Engine::Tera));
+        assert_eq!(Engine::from_path("launch.hbs"), Some(Engine::Handlebars));
+        assert_eq!(Engine::from_path("config.yaml.j2"), Some(Engine::MiniJinja));
+        assert_eq!(Engine::from_path("config.json"), None);
+    }
+
+    #[cfg(feature = "handlebars")]
+    #[test]
+    fn handlebars_helpers() {
+        let mut templates = Templates::new(Engine::Handlebars, None).unwrap();
+        assert_eq!(
+            templates
+                .render_str(
+                    "{{shell_quote name}} --{{kebab key}} {{basename path}} {{join_args files}}",
+                    &context()
+                )
+                .unwrap(),
+            "'my model' --batch-size model.pt a.txt 'b c.txt' 1"
+        );
+        assert!(templates.render_str("{{kebab files}}", &context()).is_err());
+    }
+
+    #[cfg(feature = "minijinja")]
+    #[test]
+    fn minijinja_filters() {
+        let mut templates = Templates::new(Engine::MiniJinja, None).unwrap();
+        assert_eq!(
+            templates
+                .render_str(
+                    "{{ name | shell_quote }} --{{ key | kebab }} {{ path | basename }} {{ files | join_args }}",
+                    &context()
+                )
+                .unwrap(),
+            "'my model' --batch-size model.pt a.txt 'b c.txt' 1"
+        );
+        assert!(
+            templates
+                .render_str("{{ files | kebab }}", &context())
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::str::FromStr;
+
+/// The unit which durations like `1h30m` are converted to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DurationUnit {
+    #[default]
+    Seconds,
+    Milliseconds,
+}
+
+impl FromStr for DurationUnit {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<DurationUnit> {
+        match name {
+            "s" => Ok(DurationUnit::Seconds),
+            "ms" => Ok(DurationUnit::Milliseconds),
+            _ => anyhow::bail!("unsupported duration unit: {name}"),
+        }
+    }
+}
+
+/// Parses a duration made of numbers followed by `d`, `h`, `m`, `s` or `ms`, like `5m`,
+/// `1h30m` or `1.5s`, into the unit. Returns `None` for anything else.
+pub fn parse_duration(text: &str, unit: DurationUnit) -> Option<f64> {
+    if text.is_empty() {
+        return None;
+    }
+    let mut milliseconds = 0.0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (number, suffix) = split_number(rest)?;
+        let end = suffix
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(suffix.len());
+        let scale = match &suffix[..end] {
+            "d" => 86_400_000.0,
+            "h" => 3_600_000.0,
+            "m" => 60_000.0,
+            "s" => 1_000.0,
+            "ms" => 1.0,
+            _ => return None,
+        };
+        milliseconds += number * scale;
+        rest = &suffix[end..];
+    }
+
+    let duration = match unit {
+        DurationUnit::Seconds => milliseconds / 1_000.0,
+        DurationUnit::Milliseconds => milliseconds,
+    };
+    Some(round(duration))
+}
+
+/// Parses a size followed by a unit, like `2GiB`, `512k` or `1.5MB`, into a number of
+/// bytes. `k`, `M`, `G`, `T` and `P` are powers of 1000 and `Ki`, `Mi`, `Gi`, `Ti` and
+/// `Pi` are powers of 1024, optionally followed by `B`. Returns `None` for anything else.
+pub fn parse_size(text: &str) -> Option<u64> {
+    let (number, unit) = split_number(text)?;
+    let unit = unit.strip_suffix(['B', 'b']).unwrap_or(unit);
+    let (prefix, base) = match unit.strip_suffix('i') {
+        Some(prefix) if !prefix.is_empty() => (prefix, 1024.0_f64),
+        _ => (unit, 1000.0),
+    };
+    let exponent = match prefix.to_ascii_lowercase().as_str() {
+        "" => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        "p" => 5,
+        _ => return None,
+    };
+
+    let bytes = (number * base.powi(exponent)).round();
+    (bytes <= u64::MAX as f64).then_some(bytes as u64)
+}
+
+/// Splits the leading decimal number of a text from the rest of it.
+fn split_number(text: &str) -> Option<(f64, &str)> {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let number = text[..end].parse().ok()?;
+    Some((number, &text[end..]))
+}
+
+/// Rounds away the error of multiplying decimal numbers, like `1100.0000000000002` for
+/// `1.1s` in milliseconds.
+fn round(number: f64) -> f64 {
+    (number * 1e9).round() / 1e9
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(parse_duration("5m", DurationUnit::Seconds), Some(300.0));
+        assert_eq!(parse_duration("1h30m", DurationUnit::Seconds), Some(5400.0));
+        assert_eq!(parse_duration("1.5s", DurationUnit::Seconds), Some(1.5));
+        assert_eq!(parse_duration("250ms", DurationUnit::Seconds), Some(0.25));
+        assert_eq!(
+            parse_duration("1.1s", DurationUnit::Milliseconds),
+            Some(1100.0)
+        );
+        assert_eq!(
+            parse_duration("2d", DurationUnit::Milliseconds),
+            Some(172_800_000.0)
+        );
+        assert_eq!(parse_duration("", DurationUnit::Seconds), None);
+        assert_eq!(parse_duration("5", DurationUnit::Seconds), None);
+        assert_eq!(parse_duration("5 m", DurationUnit::Seconds), None);
+        assert_eq!(parse_duration("fast", DurationUnit::Seconds), None);
+    }
+
+    #[test]
+    fn parse_sizes() {
+        assert_eq!(parse_size("2GiB"), Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("512k"), Some(512_000));
+        assert_eq!(parse_size("512Ki"), Some(512 * 1024));
+        assert_eq!(parse_size("1.5MB"), Some(1_500_000));
+        assert_eq!(parse_size("64"), Some(64));
+        assert_eq!(parse_size("64B"), Some(64));
+        assert_eq!(parse_size("2i"), None);
+        assert_eq!(parse_size("2 GiB"), None);
+        assert_eq!(parse_size("large"), None);
+    }
+}
@@ -0,0 +1,101 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::env;
+
+/// Prefix of the string values which refer to a Vault secret, like
+/// `vault:secret/data/db#password`.
+pub const PREFIX: &str = "vault:";
+
+/// Replaces every `vault:PATH#FIELD` string in the config with the field of the
+/// secret at PATH, read from `VAULT_ADDR` with `VAULT_TOKEN`. Both KV version 1 and
+/// version 2 (`secret/data/...`) secrets are supported.
+pub fn resolve_secrets(config: &mut Value) -> anyhow::Result<()> {
+    resolve(config, &mut HashMap::new(), &mut fetch)
+}
+
+fn resolve(
+    value: &mut Value,
+    secrets: &mut HashMap<String, Value>,
+    fetch: &mut impl FnMut(&str) -> anyhow::Result<Value>,
+) -> anyhow::Result<()> {
+    match value {
+        Value::String(text) => {
+            if let Some(reference) = text.strip_prefix(PREFIX) {
+                let (path, field) = reference
+                    .split_once('#')
+                    .ok_or_else(|| anyhow::anyhow!("{text}: expected {PREFIX}PATH#FIELD"))?;
+                if !secrets.contains_key(path) {
+                    secrets.insert(path.to_string(), fetch(path)?);
+                }
+
+                let data = &secrets[path]["data"];
+                let data = if data["data"].is_object() {
+                    &data["data"]
+                } else {
+                    data
+                };
+                let secret = data
+                    .get(field)
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("{text}: field `{field}` is not found"))?;
+                *value = secret;
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                resolve(item, secrets, fetch)?;
+            }
+        }
+        Value::Object(object) => {
+            for (_, item) in object.iter_mut() {
+                resolve(item, secrets, fetch)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn fetch(path: &str) -> anyhow::Result<Value> {
+    let address = env::var("VAULT_ADDR").map_err(|_| anyhow::anyhow!("VAULT_ADDR is not set"))?;
+    let token = env::var("VAULT_TOKEN").map_err(|_| anyhow::anyhow!("VAULT_TOKEN is not set"))?;
+
+    let url = format!("{}/v1/{path}", address.trim_end_matches('/'));
+    let response = ureq::get(&url)
+        .set("X-Vault-Token", &token)
+        .call()
+        .map_err(|error| anyhow::anyhow!("{url}: {error}"))?;
+
+    Ok(serde_json::from_reader(response.into_reader())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn resolve_kv_secrets() {
+        let mut config = json!({
+            "user": "vault:secret/db#user",
+            "db": {"password": "vault:secret/data/db#password"},
+            "out": "logs"
+        });
+        let mut fetched = Vec::new();
+        let mut fetch = |path: &str| {
+            fetched.push(path.to_string());
+            Ok(match path {
+                "secret/db" => json!({"data": {"user": "admin"}}),
+                _ => json!({"data": {"data": {"password": "p@ss"}, "metadata": {}}}),
+            })
+        };
+
+        resolve(&mut config, &mut HashMap::new(), &mut fetch).unwrap();
+        assert_eq!(
+            config,
+            json!({"user": "admin", "db": {"password": "p@ss"}, "out": "logs"})
+        );
+        assert_eq!(fetched, vec!["secret/db", "secret/data/db"]);
+    }
+}